@@ -0,0 +1,72 @@
+//! Runs repeated `generate` cycles across several settings extension binaries, printing a report of
+//! the simulation as JSON.
+//!
+//! ```text
+//! settings-generation-simulator \
+//!     --extension hostname@v1=/usr/libexec/settings/hostname \
+//!     --extension motd@v1=/usr/libexec/settings/motd
+//! ```
+use argh::FromArgs;
+use bottlerocket_settings_sdk::client::ExtensionClient;
+use bottlerocket_settings_sdk::simulator::{
+    BinaryExtension, GenerationSimulatorBuilder, SimulationOutcome, DEFAULT_MAX_ROUNDS,
+};
+use std::process::ExitCode;
+
+/// Simulate the settings system's generation cycles across several settings extension binaries.
+#[derive(FromArgs, Debug)]
+struct Args {
+    /// an extension to simulate, given as `<name>@<setting-version>=<path>`
+    #[argh(option, from_str_fn(parse_extension))]
+    extension: Vec<BinaryExtension>,
+
+    /// the maximum number of generation rounds to run
+    #[argh(option, default = "DEFAULT_MAX_ROUNDS")]
+    max_rounds: usize,
+}
+
+fn parse_extension(arg: &str) -> Result<BinaryExtension, String> {
+    let (setting, path) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Extension '{arg}' must be given as <name>@<version>=<path>"))?;
+    let (name, version) = setting
+        .split_once('@')
+        .ok_or_else(|| format!("Extension '{arg}' must be given as <name>@<version>=<path>"))?;
+
+    Ok(BinaryExtension::new(
+        name,
+        version,
+        ExtensionClient::new(path),
+    ))
+}
+
+fn main() -> ExitCode {
+    let args: Args = argh::from_env();
+
+    let simulator = args
+        .extension
+        .into_iter()
+        .fold(
+            GenerationSimulatorBuilder::new(),
+            GenerationSimulatorBuilder::with_extension,
+        )
+        .with_max_rounds(args.max_rounds)
+        .build();
+    let report = match simulator {
+        Ok(simulator) => simulator.run(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Failed to serialize simulation report")
+    );
+
+    match report.outcome {
+        SimulationOutcome::Complete => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
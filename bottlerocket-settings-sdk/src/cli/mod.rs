@@ -7,10 +7,23 @@ pub mod proto1;
 
 use argh::FromArgs;
 use std::fmt::Display;
+use std::str::FromStr;
+use tracing::level_filters::LevelFilter;
 
 /// Provides a CLI interface to the settings extension.
+///
+/// The logging flags are always accepted, so that callers can pass them to any extension, but are
+/// ignored unless the extension is built with the `logging` feature.
 #[derive(FromArgs, Debug)]
 pub struct Cli {
+    /// the most verbose level of logs to write to stderr: off, error, warn, info, debug or trace
+    #[argh(option)]
+    pub log_level: Option<LevelFilter>,
+
+    /// the format of logs written to stderr: text or json
+    #[argh(option)]
+    pub log_format: Option<LogFormat>,
+
     /// the Bottlerocket Settings CLI protocol to use
     #[argh(subcommand)]
     pub protocol: Protocol,
@@ -32,3 +45,36 @@ impl Display for Protocol {
         })
     }
 }
+
+/// The format of logs written by the settings extension.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines of text
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown log format '{s}', expected 'text' or 'json'"
+            )),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        })
+    }
+}
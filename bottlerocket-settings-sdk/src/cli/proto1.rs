@@ -1,6 +1,9 @@
 //! Bottlerocket Settings Extension CLI proto1 definition.
 #![allow(missing_docs)]
+use crate::helper::{HelperContext, HelperInvocation};
 use argh::FromArgs;
+use std::fmt::Display;
+use std::str::FromStr;
 
 /// Use Settings Extension CLI protocol proto1.
 #[derive(FromArgs, Debug)]
@@ -12,12 +15,18 @@ pub struct Protocol1 {
 }
 
 /// The command to invoke against the settings extension.
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand)]
 pub enum Proto1Command {
     /// Modify values owned by this setting
     Set(SetCommand),
 
+    /// Remove the value of this setting, or reset it
+    Unset(UnsetCommand),
+
+    /// Describe the changes between the current value of this setting and a new value
+    Diff(DiffCommand),
+
     /// Generate default values for this setting
     Generate(GenerateCommand),
 
@@ -32,12 +41,363 @@ pub enum Proto1Command {
 
     ///  Execute a helper. Typically this is used to render config templates
     Helper(TemplateHelperCommand),
+
+    /// List the template helpers available to this setting, along with their signatures
+    Helpers(ListHelpersCommand),
+
+    /// Render a config template using this setting's helpers
+    #[cfg(feature = "render")]
+    Render(RenderCommand),
 }
 
-impl Proto1Command {}
+impl Proto1Command {
+    /// The names of all proto1 commands, as returned by [`Proto1Command::name`].
+    pub const NAMES: &'static [&'static str] = &[
+        "set",
+        "unset",
+        "diff",
+        "generate",
+        "validate",
+        "migrate",
+        "flood-migrate",
+        "helper",
+        "helpers",
+        #[cfg(feature = "render")]
+        "render",
+    ];
+
+    /// Returns the name of the command, e.g. "set".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Set(_) => "set",
+            Self::Unset(_) => "unset",
+            Self::Diff(_) => "diff",
+            Self::Generate(_) => "generate",
+            Self::Validate(_) => "validate",
+            Self::Migrate(_) => "migrate",
+            Self::FloodMigrate(_) => "flood-migrate",
+            Self::Helper(_) => "helper",
+            Self::Helpers(_) => "helpers",
+            #[cfg(feature = "render")]
+            Self::Render(_) => "render",
+        }
+    }
+
+    /// Returns the version of the setting which the command acts on.
+    ///
+    /// For migrations, this is the version being migrated from.
+    pub fn setting_version(&self) -> &str {
+        match self {
+            Self::Set(s) => &s.setting_version,
+            Self::Unset(u) => &u.setting_version,
+            Self::Diff(d) => &d.setting_version,
+            Self::Generate(g) => &g.setting_version,
+            Self::Validate(v) => &v.setting_version,
+            Self::Migrate(m) => &m.from_version,
+            Self::FloodMigrate(m) => &m.from_version,
+            Self::Helper(h) => &h.setting_version,
+            Self::Helpers(h) => &h.setting_version,
+            #[cfg(feature = "render")]
+            Self::Render(r) => &r.setting_version,
+        }
+    }
+
+    /// Returns the command line arguments which invoke this command, excluding the protocol name.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |flag: &str, value: String| {
+            args.push(flag.to_string());
+            args.push(value);
+        };
+
+        match self {
+            Self::Set(s) => {
+                push("--setting-version", s.setting_version.clone());
+                if let Some(value) = &s.value {
+                    push("--value", value.to_string());
+                }
+                if let Some(merge_patch) = &s.merge_patch {
+                    push("--merge-patch", merge_patch.to_string());
+                }
+                if let Some(json_patch) = &s.json_patch {
+                    push("--json-patch", json_patch.to_string());
+                }
+                if let Some(current_value) = &s.current_value {
+                    push("--current-value", current_value.to_string());
+                }
+                if let Some(target_version) = &s.target_version {
+                    push("--target-version", target_version.clone());
+                }
+            }
+            Self::Unset(u) => {
+                push("--setting-version", u.setting_version.clone());
+                if let Some(current_value) = &u.current_value {
+                    push("--current-value", current_value.to_string());
+                }
+            }
+            Self::Diff(d) => {
+                push("--setting-version", d.setting_version.clone());
+                push("--value", d.value.to_string());
+                if let Some(current_value) = &d.current_value {
+                    push("--current-value", current_value.to_string());
+                }
+            }
+            Self::Generate(g) => {
+                push("--setting-version", g.setting_version.clone());
+                if let Some(existing_partial) = &g.existing_partial {
+                    push("--existing-partial", existing_partial.to_string());
+                }
+                if let Some(required_settings) = &g.required_settings {
+                    push("--required-settings", required_settings.to_string());
+                }
+            }
+            Self::Validate(v) => {
+                push("--setting-version", v.setting_version.clone());
+                push("--value", v.value.to_string());
+                if let Some(required_settings) = &v.required_settings {
+                    push("--required-settings", required_settings.to_string());
+                }
+                if let Some(target_version) = &v.target_version {
+                    push("--target-version", target_version.clone());
+                }
+            }
+            Self::Migrate(m) => {
+                push("--value", m.value.to_string());
+                push("--from-version", m.from_version.clone());
+                push("--target-version", m.target_version.clone());
+                if let Some(preserved_data) = &m.preserved_data {
+                    push("--preserved-data", preserved_data.to_string());
+                }
+                if m.preserve {
+                    args.push("--preserve".to_string());
+                }
+            }
+            Self::FloodMigrate(m) => {
+                push("--value", m.value.to_string());
+                push("--from-version", m.from_version.clone());
+            }
+            Self::Helper(h) => {
+                push("--setting-version", h.setting_version.clone());
+                push("--helper-name", h.helper_name.clone());
+                h.arg.iter().for_each(|arg| push("--arg", arg.to_string()));
+                h.hash
+                    .iter()
+                    .for_each(|hash_arg| push("--hash", hash_arg.to_string()));
+                if let Some(value) = &h.value {
+                    push("--value", value.to_string());
+                }
+                if let Some(required_settings) = &h.required_settings {
+                    push("--required-settings", required_settings.to_string());
+                }
+            }
+            Self::Helpers(h) => {
+                push("--setting-version", h.setting_version.clone());
+            }
+            #[cfg(feature = "render")]
+            Self::Render(r) => {
+                push("--setting-version", r.setting_version.clone());
+                push("--template", r.template.clone());
+                push("--value", r.value.to_string());
+                if let Some(required_settings) = &r.required_settings {
+                    push("--required-settings", required_settings.to_string());
+                }
+            }
+        }
+
+        std::iter::once(self.name().to_string())
+            .chain(args)
+            .collect()
+    }
+
+    /// Creates a `set` command which checks a complete new value against the current value.
+    pub fn set(
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Set(SetCommand {
+            value: Some(value),
+            ..SetCommand::new(setting_version, current_value)
+        })
+    }
+
+    /// Creates a `set` command whose new and current values are given in `setting_version`, and
+    /// are migrated to `target_version` before being checked.
+    pub fn set_with_target_version(
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Set(SetCommand {
+            value: Some(value),
+            target_version: Some(target_version.to_string()),
+            ..SetCommand::new(setting_version, current_value)
+        })
+    }
+
+    /// Creates a `set` command which applies a JSON merge patch to the current value.
+    pub fn set_merge_patch(
+        setting_version: &str,
+        merge_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Set(SetCommand {
+            merge_patch: Some(merge_patch),
+            ..SetCommand::new(setting_version, current_value)
+        })
+    }
+
+    /// Creates a `set` command which applies a JSON patch to the current value.
+    pub fn set_json_patch(
+        setting_version: &str,
+        json_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Set(SetCommand {
+            json_patch: Some(json_patch),
+            ..SetCommand::new(setting_version, current_value)
+        })
+    }
+
+    /// Creates an `unset` command.
+    pub fn unset(setting_version: &str, current_value: Option<serde_json::Value>) -> Self {
+        Self::Unset(UnsetCommand {
+            setting_version: setting_version.to_string(),
+            current_value,
+        })
+    }
+
+    /// Creates a `diff` command.
+    pub fn diff(
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Diff(DiffCommand {
+            setting_version: setting_version.to_string(),
+            value,
+            current_value,
+        })
+    }
+
+    /// Creates a `generate` command.
+    pub fn generate(
+        setting_version: &str,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Generate(GenerateCommand {
+            setting_version: setting_version.to_string(),
+            existing_partial,
+            required_settings,
+        })
+    }
+
+    /// Creates a `validate` command.
+    pub fn validate(
+        setting_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Validate(ValidateCommand {
+            setting_version: setting_version.to_string(),
+            value,
+            required_settings,
+            target_version: None,
+        })
+    }
+
+    /// Creates a `validate` command whose value is given in `setting_version`, and is migrated to
+    /// `target_version` before being validated.
+    pub fn validate_with_target_version(
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Validate(ValidateCommand {
+            setting_version: setting_version.to_string(),
+            value,
+            required_settings,
+            target_version: Some(target_version.to_string()),
+        })
+    }
+
+    /// Creates a `migrate` command which does not preserve dropped data.
+    pub fn migrate(value: serde_json::Value, from_version: &str, target_version: &str) -> Self {
+        Self::Migrate(MigrateCommand {
+            value,
+            from_version: from_version.to_string(),
+            target_version: target_version.to_string(),
+            preserve: false,
+            preserved_data: None,
+        })
+    }
+
+    /// Creates a `flood-migrate` command.
+    pub fn flood_migrate(value: serde_json::Value, from_version: &str) -> Self {
+        Self::FloodMigrate(FloodMigrateCommand {
+            value,
+            from_version: from_version.to_string(),
+        })
+    }
+
+    /// Creates a `helper` command from a list of positional arguments, or from a
+    /// [`HelperInvocation`] which also contains named arguments and the context of the setting.
+    ///
+    /// The context's version is ignored in favor of `setting_version`.
+    pub fn helper<I: Into<HelperInvocation>>(
+        setting_version: &str,
+        helper_name: &str,
+        invocation: I,
+    ) -> Self {
+        let invocation = invocation.into();
+        let context = invocation.context.unwrap_or_default();
+        Self::Helper(TemplateHelperCommand {
+            setting_version: setting_version.to_string(),
+            helper_name: helper_name.to_string(),
+            arg: invocation.args,
+            hash: invocation
+                .hash
+                .into_iter()
+                .map(|(name, value)| HashArg { name, value })
+                .collect(),
+            value: context.value,
+            required_settings: context.dependent_settings,
+        })
+    }
+
+    /// Creates a `helpers` command.
+    pub fn list_helpers(setting_version: &str) -> Self {
+        Self::Helpers(ListHelpersCommand {
+            setting_version: setting_version.to_string(),
+        })
+    }
+
+    /// Creates a `render` command.
+    #[cfg(feature = "render")]
+    pub fn render(
+        setting_version: &str,
+        template: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Self {
+        Self::Render(RenderCommand {
+            setting_version: setting_version.to_string(),
+            template: template.to_string(),
+            value,
+            required_settings,
+        })
+    }
+}
 
 /// Validates that a new setting value can be persisted to the Bottlerocket datastore.
-#[derive(FromArgs, Debug)]
+///
+/// The new value is given either in full, or as a patch against the current value. If a target
+/// version is given, the new and current values are migrated from the setting version to the target
+/// version before being checked.
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "set")]
 pub struct SetCommand {
     /// the version of the setting which should be used
@@ -46,6 +406,62 @@ pub struct SetCommand {
 
     /// the requested value to be set for the incoming setting
     #[argh(option)]
+    pub value: Option<serde_json::Value>,
+
+    /// an RFC 7396 JSON merge patch to apply to the current value, in place of a complete value
+    #[argh(option)]
+    pub merge_patch: Option<serde_json::Value>,
+
+    /// an RFC 6902 JSON patch to apply to the current value, in place of a complete value
+    #[argh(option)]
+    pub json_patch: Option<serde_json::Value>,
+
+    /// the current value of this settings tree
+    #[argh(option)]
+    pub current_value: Option<serde_json::Value>,
+
+    /// the version in which the value is stored, if different from the setting version
+    #[argh(option)]
+    pub target_version: Option<String>,
+}
+
+impl SetCommand {
+    /// Creates a `set` command which gives no new value or patch, to be completed by the caller.
+    fn new(setting_version: &str, current_value: Option<serde_json::Value>) -> Self {
+        Self {
+            setting_version: setting_version.to_string(),
+            value: None,
+            merge_patch: None,
+            json_patch: None,
+            current_value,
+            target_version: None,
+        }
+    }
+}
+
+/// Determines whether the value of this setting can be removed, or what should replace it.
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "unset")]
+pub struct UnsetCommand {
+    /// the version of the setting which should be used
+    #[argh(option)]
+    pub setting_version: String,
+
+    /// the current value of this settings tree
+    #[argh(option)]
+    pub current_value: Option<serde_json::Value>,
+}
+
+/// Describes the changes between the current value of this setting and a new value.
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "diff")]
+pub struct DiffCommand {
+    /// the version of the setting which should be used
+    #[argh(option)]
+    pub setting_version: String,
+
+    /// the new value to compare against the current value
+    #[argh(option)]
     pub value: serde_json::Value,
 
     /// the current value of this settings tree
@@ -54,7 +470,7 @@ pub struct SetCommand {
 }
 
 /// Dynamically generates a value for this setting given, possibly from other settings.
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "generate")]
 pub struct GenerateCommand {
     /// the version of the setting which should be used
@@ -71,7 +487,10 @@ pub struct GenerateCommand {
 }
 
 /// Validates an incoming setting, possibly cross-validated with other settings.
-#[derive(FromArgs, Debug)]
+///
+/// If a target version is given, the value is migrated from the setting version to the target
+/// version before being validated.
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "validate")]
 pub struct ValidateCommand {
     /// the version of the setting which should be used
@@ -85,10 +504,14 @@ pub struct ValidateCommand {
     /// a json value containing any requested settings partials needed to generate this one
     #[argh(option)]
     pub required_settings: Option<serde_json::Value>,
+
+    /// the version in which the value is stored, if different from the setting version
+    #[argh(option)]
+    pub target_version: Option<String>,
 }
 
 /// Migrates a setting value from one version to another.
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "migrate")]
 pub struct MigrateCommand {
     /// a json value containing the current value of the setting
@@ -102,10 +525,18 @@ pub struct MigrateCommand {
     /// the desired resulting version for the settings data
     #[argh(option)]
     pub target_version: String,
+
+    /// return data dropped by the migration alongside the migrated value
+    #[argh(switch)]
+    pub preserve: bool,
+
+    /// data preserved by a previous migration, to be restored where possible
+    #[argh(option)]
+    pub preserved_data: Option<serde_json::Value>,
 }
 
 /// Migrates a setting value from one version to all other known versions.
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "flood-migrate")]
 pub struct FloodMigrateCommand {
     /// a json value containing the current value of the setting
@@ -118,7 +549,7 @@ pub struct FloodMigrateCommand {
 }
 
 /// Executes a template helper to assist in rendering values to a configuration file.
-#[derive(FromArgs, Debug)]
+#[derive(FromArgs, Debug, Clone)]
 #[argh(subcommand, name = "helper")]
 pub struct TemplateHelperCommand {
     /// the version of the setting which should be used
@@ -132,4 +563,99 @@ pub struct TemplateHelperCommand {
     /// the arguments for the given helper
     #[argh(option)]
     pub arg: Vec<serde_json::Value>,
+
+    /// the named arguments for the given helper, given as `<name>=<json value>`
+    #[argh(option)]
+    pub hash: Vec<HashArg>,
+
+    /// a json value containing the current value of this setting, for context-aware helpers
+    #[argh(option)]
+    pub value: Option<serde_json::Value>,
+
+    /// a json value containing the settings this setting depends on, for context-aware helpers
+    #[argh(option)]
+    pub required_settings: Option<serde_json::Value>,
+}
+
+impl TemplateHelperCommand {
+    /// Returns the positional and named arguments given to the helper.
+    pub fn invocation(&self) -> HelperInvocation {
+        HelperInvocation {
+            args: self.arg.clone(),
+            hash: self
+                .hash
+                .iter()
+                .map(|hash_arg| (hash_arg.name.clone(), hash_arg.value.clone()))
+                .collect(),
+            context: Some(HelperContext {
+                version: self.setting_version.clone(),
+                value: self.value.clone(),
+                dependent_settings: self.required_settings.clone(),
+            }),
+        }
+    }
+}
+
+/// Lists the template helpers available to this setting.
+///
+/// The output maps each helper's name to its signature, or `null` if the helper does not describe
+/// its signature.
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "helpers")]
+pub struct ListHelpersCommand {
+    /// the version of the setting which should be used
+    #[argh(option)]
+    pub setting_version: String,
+}
+
+/// A named argument for a template helper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashArg {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+impl FromStr for HashArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("named argument '{s}' must be given as <name>=<json value>"))?;
+        let value = serde_json::from_str(value)
+            .map_err(|e| format!("named argument '{name}' is not valid JSON: {e}"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+impl Display for HashArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)
+    }
+}
+
+/// Renders a Handlebars config template using the template helpers of this setting.
+#[cfg(feature = "render")]
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "render")]
+pub struct RenderCommand {
+    /// the version of the setting which should be used
+    #[argh(option)]
+    pub setting_version: String,
+
+    /// the template to render
+    #[argh(option)]
+    pub template: String,
+
+    /// a json value containing the value of this setting
+    #[argh(option)]
+    pub value: serde_json::Value,
+
+    /// a json value containing any other settings referred to by the template
+    #[argh(option)]
+    pub required_settings: Option<serde_json::Value>,
 }
@@ -0,0 +1,346 @@
+//! Provides the [`ExtensionClient`], which invokes settings extensions as subprocesses using the
+//! settings extension CLI protocol.
+//!
+//! The client speaks the same protocol that the [`SettingsExtension`](crate::SettingsExtension)
+//! serves, and returns results using the same types that the SDK uses.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use bottlerocket_settings_sdk::client::ExtensionClient;
+//! use bottlerocket_settings_sdk::GenerateResult;
+//! use serde_json::json;
+//!
+//! let client = ExtensionClient::new("/usr/libexec/settings/motd");
+//!
+//! client.set("v1", json!("Hello!"), None).unwrap();
+//!
+//! let generated: GenerateResult<serde_json::Value, serde_json::Value> =
+//!     client.generate("v1", None, None).unwrap();
+//! ```
+use crate::cli::proto1::Proto1Command;
+use crate::helper::{HelperInvocation, HelperSignature};
+use crate::{GenerateResult, MigrationResult, SettingsDiff, UnsetResult};
+use serde::de::DeserializeOwned;
+use snafu::{ensure, ResultExt};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, instrument};
+
+pub use error::ExtensionClientError;
+
+type Result<T> = std::result::Result<T, ExtensionClientError>;
+
+/// A client which invokes a settings extension binary using CLI protocol "proto1".
+#[derive(Debug, Clone)]
+pub struct ExtensionClient {
+    path: PathBuf,
+}
+
+impl ExtensionClient {
+    /// Creates a client for the settings extension binary at the given path.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path to the settings extension binary.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs a proto1 command against the settings extension, returning its raw output.
+    ///
+    /// The command's arguments are not logged, as they may contain sensitive values.
+    #[instrument(
+        skip(self, command),
+        fields(
+            extension = %self.path.display(),
+            command = command.name(),
+            setting_version = command.setting_version(),
+        ),
+        err
+    )]
+    pub fn run(&self, command: Proto1Command) -> Result<String> {
+        let args = command.to_args();
+        let command_name = command.name().to_string();
+        debug!("Invoking settings extension.");
+
+        let output = Command::new(&self.path)
+            .arg("proto1")
+            .args(&args)
+            .output()
+            .context(error::SpawnExtensionSnafu {
+                path: self.path.clone(),
+            })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        ensure!(
+            output.status.success(),
+            error::CommandFailedSnafu {
+                command: command_name,
+                status: output.status,
+                message: if stdout.is_empty() {
+                    String::from_utf8_lossy(&output.stderr).trim().to_string()
+                } else {
+                    stdout
+                },
+            }
+        );
+
+        Ok(stdout)
+    }
+
+    /// Checks whether the setting can be set to `value`, given its current value.
+    pub fn set(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::set(setting_version, value, current_value))
+            .map(|_| ())
+    }
+
+    /// Checks whether the setting can be set to `value`, given its current value, where both are
+    /// given in `setting_version` and migrated to `target_version`, the version in which the
+    /// setting is stored.
+    pub fn set_with_target_version(
+        &self,
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::set_with_target_version(
+            setting_version,
+            target_version,
+            value,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Checks whether the setting can be set to the result of applying a JSON merge patch to its
+    /// current value.
+    pub fn set_merge_patch(
+        &self,
+        setting_version: &str,
+        merge_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::set_merge_patch(
+            setting_version,
+            merge_patch,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Checks whether the setting can be set to the result of applying a JSON patch to its current
+    /// value.
+    pub fn set_json_patch(
+        &self,
+        setting_version: &str,
+        json_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::set_json_patch(
+            setting_version,
+            json_patch,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Checks whether the setting's value can be removed, given its current value, or what should
+    /// replace it.
+    pub fn unset<T: DeserializeOwned>(
+        &self,
+        setting_version: &str,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<UnsetResult<T>> {
+        self.run_parsed(Proto1Command::unset(setting_version, current_value))
+    }
+
+    /// Describes the changes between the setting's current value and a new value.
+    pub fn diff(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<SettingsDiff> {
+        self.run_parsed(Proto1Command::diff(setting_version, value, current_value))
+    }
+
+    /// Generates a value for the setting.
+    pub fn generate<P, C>(
+        &self,
+        setting_version: &str,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<P, C>>
+    where
+        P: DeserializeOwned,
+        C: DeserializeOwned,
+    {
+        self.run_parsed(Proto1Command::generate(
+            setting_version,
+            existing_partial,
+            required_settings,
+        ))
+    }
+
+    /// Validates a value for the setting, possibly cross-validated with other settings.
+    pub fn validate(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::validate(
+            setting_version,
+            value,
+            required_settings,
+        ))
+        .map(|_| ())
+    }
+
+    /// Validates a value given in `setting_version`, once migrated to `target_version`.
+    pub fn validate_with_target_version(
+        &self,
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run(Proto1Command::validate_with_target_version(
+            setting_version,
+            target_version,
+            value,
+            required_settings,
+        ))
+        .map(|_| ())
+    }
+
+    /// Migrates a setting value from one version to another.
+    pub fn migrate(
+        &self,
+        value: serde_json::Value,
+        from_version: &str,
+        target_version: &str,
+    ) -> Result<serde_json::Value> {
+        self.run_parsed(Proto1Command::migrate(value, from_version, target_version))
+    }
+
+    /// Migrates a setting value from one version to all other known versions.
+    pub fn flood_migrate(
+        &self,
+        value: serde_json::Value,
+        from_version: &str,
+    ) -> Result<Vec<MigrationResult>> {
+        self.run_parsed(Proto1Command::flood_migrate(value, from_version))
+    }
+
+    /// Executes a template helper provided by the setting.
+    ///
+    /// Arguments may be given as a list of positional arguments, or as a [`HelperInvocation`] which
+    /// also contains named arguments and the context of the setting. The context's version is
+    /// ignored in favor of `setting_version`.
+    pub fn template_helper<I: Into<HelperInvocation>>(
+        &self,
+        setting_version: &str,
+        helper_name: &str,
+        invocation: I,
+    ) -> Result<serde_json::Value> {
+        self.run_parsed(Proto1Command::helper(
+            setting_version,
+            helper_name,
+            invocation,
+        ))
+    }
+
+    /// Lists the template helpers available to the setting, along with their signatures.
+    ///
+    /// Helpers which do not describe their signature are mapped to `None`.
+    pub fn list_helpers(
+        &self,
+        setting_version: &str,
+    ) -> Result<BTreeMap<String, Option<HelperSignature>>> {
+        self.run_parsed(Proto1Command::list_helpers(setting_version))
+    }
+
+    /// Renders a config template using the setting's template helpers.
+    ///
+    /// Leading and trailing whitespace is trimmed from the rendered output.
+    #[cfg(feature = "render")]
+    pub fn render(
+        &self,
+        setting_version: &str,
+        template: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<String> {
+        self.run(Proto1Command::render(
+            setting_version,
+            template,
+            value,
+            required_settings,
+        ))
+    }
+
+    /// Runs a proto1 command, parsing its output as JSON.
+    fn run_parsed<T: DeserializeOwned>(&self, command: Proto1Command) -> Result<T> {
+        let command_name = command.name().to_string();
+        let output = self.run(command)?;
+
+        // The output may contain sensitive setting values, so only its length is reported.
+        serde_json::from_str(&output).context(error::ParseOutputSnafu {
+            command: command_name,
+            output_len: output.len(),
+        })
+    }
+}
+
+mod error {
+    #![allow(missing_docs)]
+    use snafu::Snafu;
+    use std::path::PathBuf;
+    use std::process::ExitStatus;
+
+    /// The error type returned when invoking a settings extension with the
+    /// [`ExtensionClient`](super::ExtensionClient).
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility(pub))]
+    pub enum ExtensionClientError {
+        #[snafu(display(
+            "Settings extension command '{}' failed ({}): {}",
+            command,
+            status,
+            message
+        ))]
+        CommandFailed {
+            command: String,
+            status: ExitStatus,
+            message: String,
+        },
+
+        #[snafu(display(
+            "Failed to parse {}-byte output of settings extension command '{}' as JSON: {}",
+            output_len,
+            command,
+            source
+        ))]
+        ParseOutput {
+            command: String,
+            output_len: usize,
+            source: serde_json::Error,
+        },
+
+        #[snafu(display("Failed to run settings extension '{}': {}", path.display(), source))]
+        SpawnExtension {
+            path: PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
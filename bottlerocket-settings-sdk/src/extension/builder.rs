@@ -19,9 +19,14 @@
 //!     ])
 //!     .build();
 //! ```
-use super::SettingsExtensionError;
+use super::watchdog::Timeouts;
+use super::{error, CommandHook, SettingsExtensionError};
+use crate::cli::proto1::Proto1Command;
 use crate::model::erased::AsTypeErasedModel;
-use crate::{Migrator, SettingsExtension};
+use crate::{HelperDef, Migrator, SettingsExtension};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::instrument;
 
 #[macro_export]
@@ -81,6 +86,53 @@ macro_rules! extension_builder {
                 Self(inner_builder)
             }
 
+            /// Registers template helpers which are available to all model versions.
+            $vis fn with_template_helpers(
+                self,
+                helpers: std::collections::HashMap<
+                    String,
+                    Box<dyn $crate::HelperDef>,
+                >,
+            ) -> Self {
+                let Self(inner_builder) = self;
+                let inner_builder = inner_builder.with_template_helpers(helpers);
+
+                Self(inner_builder)
+            }
+
+            /// Limits the time that any command may run before the extension times out.
+            ///
+            /// Time limits are not enforced by `try_run_with_args`.
+            $vis fn with_timeout(self, timeout: std::time::Duration) -> Self {
+                let Self(inner_builder) = self;
+                let inner_builder = inner_builder.with_timeout(timeout);
+
+                Self(inner_builder)
+            }
+
+            /// Limits the time that the named command may run before the extension times out.
+            ///
+            /// Time limits are not enforced by `try_run_with_args`. Building the extension fails if
+            /// the command is not one of `Proto1Command::NAMES`.
+            $vis fn with_command_timeout(
+                self,
+                command: &str,
+                timeout: std::time::Duration,
+            ) -> Self {
+                let Self(inner_builder) = self;
+                let inner_builder = inner_builder.with_command_timeout(command, timeout);
+
+                Self(inner_builder)
+            }
+
+            /// Registers a hook which is called around every command run by the extension.
+            $vis fn with_hook<H: $crate::extension::CommandHook + 'static>(self, hook: H) -> Self {
+                let Self(inner_builder) = self;
+                let inner_builder = inner_builder.with_hook(hook);
+
+                Self(inner_builder)
+            }
+
             /// Constructs a `SettingsExtension` with the given options.
             $vis fn build(
                 self,
@@ -110,6 +162,9 @@ where
     name: &'static str,
     models: Option<Vec<Mo>>,
     migrator: Mi,
+    template_helpers: HashMap<String, Arc<dyn HelperDef>>,
+    timeouts: Timeouts,
+    hooks: Vec<Arc<dyn CommandHook>>,
 }
 
 impl<Mi, Mo> SettingsExtensionBuilder<Mi, Mo>
@@ -123,6 +178,9 @@ where
             name,
             migrator,
             models: None,
+            template_helpers: HashMap::new(),
+            timeouts: Timeouts::default(),
+            hooks: Vec::new(),
         }
     }
 
@@ -132,12 +190,71 @@ where
         self
     }
 
+    /// Registers template helpers which are available to all model versions.
+    ///
+    /// This avoids providing the same helper from each model's
+    /// [`SettingsModel::template_helpers`](crate::SettingsModel::template_helpers). Helpers
+    /// provided by a model take precedence over those registered here with the same name.
+    pub fn with_template_helpers(mut self, helpers: HashMap<String, Box<dyn HelperDef>>) -> Self {
+        self.template_helpers.extend(
+            helpers
+                .into_iter()
+                .map(|(name, helper)| (name, Arc::from(helper))),
+        );
+        self
+    }
+
+    /// Limits the time that any command may run before the extension reports a
+    /// [`Timeout`](SettingsExtensionError::Timeout) error and exits.
+    ///
+    /// Time limits are enforced when the extension is served with [`SettingsExtension::run`] or
+    /// [`SettingsExtension::run_with_args`]. They are not enforced by
+    /// [`SettingsExtension::try_run_with_args`], which returns to its caller rather than exiting.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.default = Some(timeout);
+        self
+    }
+
+    /// Limits the time that the named command, e.g. `"generate"`, may run before the extension
+    /// reports a [`Timeout`](SettingsExtensionError::Timeout) error and exits.
+    ///
+    /// This takes precedence over any limit set with
+    /// [`with_timeout`](SettingsExtensionBuilder::with_timeout), and is likewise not enforced by
+    /// [`SettingsExtension::try_run_with_args`]. The command must be one of
+    /// [`Proto1Command::NAMES`](crate::cli::proto1::Proto1Command::NAMES), or building the extension
+    /// fails.
+    pub fn with_command_timeout(mut self, command: &str, timeout: Duration) -> Self {
+        self.timeouts.commands.insert(command.to_string(), timeout);
+        self
+    }
+
+    /// Registers a hook which is called around every command run by the [`SettingsExtension`].
+    ///
+    /// Hooks are called in the order they are registered.
+    pub fn with_hook<H: CommandHook + 'static>(mut self, hook: H) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
     /// Constructs a [`SettingsExtension`] using the configurations supplied to the builder.
     #[instrument(skip(self), err)]
     pub fn build(self) -> Result<SettingsExtension<Mi, Mo>, SettingsExtensionError<Mi::ErrorKind>> {
+        if let Some(command) = self
+            .timeouts
+            .commands
+            .keys()
+            .find(|command| !Proto1Command::NAMES.contains(&command.as_str()))
+        {
+            return error::UnknownTimeoutCommandSnafu { command }.fail();
+        }
+
         let models = self.models.unwrap_or_default();
         let migrator = self.migrator;
 
-        SettingsExtension::new(self.name, models, migrator)
+        let mut extension = SettingsExtension::new(self.name, models, migrator)?;
+        extension.template_helpers = self.template_helpers;
+        extension.timeouts = self.timeouts;
+        extension.hooks = self.hooks;
+        Ok(extension)
     }
 }
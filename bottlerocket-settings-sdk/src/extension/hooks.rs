@@ -0,0 +1,49 @@
+//! Provides the [`CommandHook`] trait, which allows extensions to observe and reject commands
+//! without modifying their models.
+use crate::cli::proto1::Proto1Command;
+
+/// The error type returned by a [`CommandHook`] which rejects a command.
+pub type HookError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A hook which is called around every command run by a settings extension, e.g. for auditing,
+/// metrics or policy checks.
+///
+/// Hooks are registered with
+/// [`SettingsExtensionBuilder::with_hook`](super::SettingsExtensionBuilder::with_hook) and are
+/// called in the order they were registered.
+///
+/// If a command exceeds its time limit, hooks are called with the
+/// [`Timeout`](super::SettingsExtensionError::Timeout) error from the thread which reports it, so
+/// hooks must be `Send` and `Sync`.
+///
+/// Hooks see commands as they were given to the extension, so command values are not redacted.
+/// See [`redact`](crate::model::redact) for redacting sensitive values before recording them.
+///
+/// ```
+/// use bottlerocket_settings_sdk::cli::proto1::Proto1Command;
+/// use bottlerocket_settings_sdk::extension::{CommandHook, HookError};
+///
+/// /// Rejects any changes to a setting's oldest version.
+/// struct FreezeVersion(&'static str);
+///
+/// impl CommandHook for FreezeVersion {
+///     fn before(&self, command: &Proto1Command) -> Result<(), HookError> {
+///         match command {
+///             Proto1Command::Set(_) if command.setting_version() == self.0 => {
+///                 Err(format!("version '{}' is frozen", self.0).into())
+///             }
+///             _ => Ok(()),
+///         }
+///     }
+/// }
+/// ```
+pub trait CommandHook: Send + Sync {
+    /// Called before the command runs. Returning an error rejects the command, which fails with
+    /// a [`Hook`](super::SettingsExtensionError::Hook) error.
+    fn before(&self, _command: &Proto1Command) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    /// Called after the command has run, or been rejected, with its output or error.
+    fn after(&self, _command: &Proto1Command, _result: Result<&str, &dyn std::error::Error>) {}
+}
@@ -1,23 +1,33 @@
 //! Provides the [`SettingsExtension`] struct, which enables developers to create Bottlerocket
 //! settings extensions that adhere to the settings extension CLI protocol.
 use crate::cli;
+use crate::helper::{HelperDef, HelperError, HelperInvocation, HelperSignature};
 use crate::migrate::{Migrator, ModelStore};
-use crate::model::erased::AsTypeErasedModel;
+use crate::model::erased::{self, AsTypeErasedModel};
 use argh::FromArgs;
 use snafu::{ensure, OptionExt, ResultExt};
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
+use std::panic::AssertUnwindSafe;
 use std::process::ExitCode;
+use std::sync::Arc;
 use tracing::{debug, info};
 
 mod builder;
+mod hooks;
 mod proto1;
+mod watchdog;
 pub use self::builder::SettingsExtensionBuilder;
+pub use self::hooks::{CommandHook, HookError};
 pub use error::SettingsExtensionError;
+use watchdog::Timeouts;
 
 // Type alias to clarify intent of some strings.
 type Version = String;
 
+// Template helpers keyed by the name they are called with.
+type TemplateHelpers = HashMap<String, Box<dyn HelperDef>>;
+
 /// The Bottlerocket settings system uses executable modules, called "settings extensions", to
 /// provide different settings with customizable behavior for any given Bottlerocket variant.
 /// These settings extensions respond to the Bottlerocket Settings Extensions CLI protocol.
@@ -33,6 +43,9 @@ where
     name: &'static str,
     models: HashMap<Version, Mo>,
     migrator: Mi,
+    template_helpers: HashMap<String, Arc<dyn HelperDef>>,
+    timeouts: Timeouts,
+    hooks: Vec<Arc<dyn CommandHook>>,
 }
 
 impl<Mi, Mo> SettingsExtension<Mi, Mo>
@@ -55,6 +68,9 @@ where
             name,
             models,
             migrator,
+            template_helpers: HashMap::new(),
+            timeouts: Timeouts::default(),
+            hooks: Vec::new(),
         };
 
         extension.validate_migrations()?;
@@ -102,22 +118,83 @@ where
     ///
     /// Users of this method should not separately write to `stdout`, as this could break adherence
     /// to the settings extension CLI protocol.
+    ///
+    /// With the `logging` feature enabled, this installs a subscriber which writes logs to stderr
+    /// as described in the [`logging` module](crate::logging), unless one is already installed.
+    /// Without it, the `--log-level` and `--log-format` flags are accepted but ignored.
     pub fn run(self) -> ExitCode {
         let args: cli::Cli = argh::from_env();
+        self.serve(args)
+    }
+
+    /// Runs the extension using the given CLI input, printing results as
+    /// [`run`](SettingsExtension::run) does.
+    ///
+    /// CLI parsing errors are printed to stdout in the same form as any other error.
+    pub fn run_with_args<I, T>(self, iter: I) -> ExitCode
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        match Self::parse_args(iter) {
+            Ok(args) => self.serve(args),
+            Err(e) => {
+                println!("{}", e);
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// Serves the given CLI input, printing results to stdout.
+    fn serve(self, args: cli::Cli) -> ExitCode {
+        #[cfg(feature = "logging")]
+        Self::init_logging(&args);
         info!(extension = ?self, protocol = ?args.protocol, "Starting settings extensions");
-        debug!(?args, "CLI arguments");
 
         match args.protocol {
-            cli::Protocol::Proto1(p) => proto1::run_extension(self, p.command),
+            cli::Protocol::Proto1(p) => {
+                // Command arguments may contain sensitive values, so only the command is logged.
+                debug!(command = p.command.name(), "CLI arguments");
+                proto1::run_extension(self, p.command)
+            }
         }
     }
 
+    /// Installs the stderr logger configured by the environment and the given CLI arguments.
+    #[cfg(feature = "logging")]
+    fn init_logging(args: &cli::Cli) {
+        let config = crate::logging::LogConfig::from_env().unwrap_or_else(|e| {
+            eprintln!("Ignoring logging environment variables: {}", e);
+            Default::default()
+        });
+        // An existing subscriber means the extension configured its own logging.
+        let _ = config.with_cli_args(args).try_init();
+    }
+
     /// Runs the extension using the given CLI input and deferring behavior to the provided models,
     /// migrator, and helpers.
+    ///
+    /// Logging flags are accepted, but no logger is installed. Command time limits are not enforced,
+    /// as the caller is expected to manage its own time limits.
     pub fn try_run_with_args<I, T>(
         self,
         iter: I,
     ) -> Result<String, SettingsExtensionError<Mi::ErrorKind>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args = Self::parse_args(iter)?;
+
+        info!(cli_protocol = %args.protocol, "Starting settings extensions.");
+
+        match args.protocol {
+            cli::Protocol::Proto1(p) => proto1::try_run_extension(self, p.command),
+        }
+    }
+
+    /// Parses the given CLI input, the first element of which is the name of the extension.
+    fn parse_args<I, T>(iter: I) -> Result<cli::Cli, SettingsExtensionError<Mi::ErrorKind>>
     where
         I: IntoIterator<Item = T>,
         T: Into<OsString> + Clone,
@@ -131,17 +208,11 @@ where
         let command_name = [input_iter.next().context(error::ParseCLICommandSnafu)?];
         let args: Vec<&str> = input_iter.collect();
 
-        let args = cli::Cli::from_args(&command_name, &args).map_err(|e| {
+        cli::Cli::from_args(&command_name, &args).map_err(|e| {
             error::SettingsExtensionError::ParseCLIArgs {
                 parser_output: e.output,
             }
-        })?;
-
-        info!(cli_protocol = %args.protocol, "Starting settings extensions.");
-
-        match args.protocol {
-            cli::Protocol::Proto1(p) => proto1::try_run_extension(self, p.command),
-        }
+        })
     }
 
     /// Returns a settings model with the given version.
@@ -149,10 +220,89 @@ where
         self.models.get(version)
     }
 
+    /// Returns the name of the setting provided by this extension.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Returns the migrator used by this extension.
+    pub fn migrator(&self) -> &Mi {
+        &self.migrator
+    }
+
     /// Returns an iterator over all stored models, with no guaranteed order.
     pub fn iter_models(&self) -> impl Iterator<Item = (&str, &Mo)> {
         self.models.iter().map(|(k, v)| (k.as_str(), v))
     }
+
+    /// Returns the template helpers available to the model with the given version.
+    ///
+    /// These are the helpers registered for all model versions, along with those provided by the
+    /// model itself. Helpers provided by the model take precedence over those of the same name
+    /// registered for all versions.
+    pub fn template_helpers(
+        &self,
+        version: &str,
+    ) -> Result<TemplateHelpers, SettingsExtensionError<Mi::ErrorKind>> {
+        let mut helpers = self
+            .model(version)
+            .context(error::NoSuchModelSnafu {
+                setting_version: version,
+            })?
+            .as_model()
+            .template_helpers()
+            .context(error::TemplateHelperSnafu)?;
+
+        for (name, helper) in &self.template_helpers {
+            helpers
+                .entry(name.clone())
+                .or_insert_with(|| Box::new(SharedHelper(Arc::clone(helper))));
+        }
+
+        Ok(helpers)
+    }
+
+    /// Executes the named template helper available to the model with the given version.
+    ///
+    /// See [`SettingsExtension::template_helpers`] for the helpers which are available.
+    pub fn execute_template_helper(
+        &self,
+        version: &str,
+        helper_name: &str,
+        invocation: HelperInvocation,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Mi::ErrorKind>> {
+        let model_version = self
+            .model(version)
+            .context(error::NoSuchModelSnafu {
+                setting_version: version,
+            })?
+            .as_model()
+            .get_version();
+        let helpers = self.template_helpers(version)?;
+
+        erased::execute_template_helper(&helpers, helper_name, model_version, invocation)
+            .context(error::TemplateHelperSnafu)
+    }
+}
+
+/// A template helper registered for all model versions of an extension.
+///
+/// The helper is shared by each set of helpers returned by
+/// [`SettingsExtension::template_helpers`].
+struct SharedHelper(Arc<dyn HelperDef>);
+
+impl HelperDef for SharedHelper {
+    fn helper_fn(&self, args: Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError> {
+        self.0.helper_fn(args)
+    }
+
+    fn execute(&self, invocation: HelperInvocation) -> Result<serde_json::Value, HelperError> {
+        self.0.execute(invocation)
+    }
+
+    fn signature(&self) -> Option<HelperSignature> {
+        self.0.signature()
+    }
 }
 
 impl<Mi, Mo> ModelStore for SettingsExtension<Mi, Mo>
@@ -175,6 +325,20 @@ where
     }
 }
 
+/// Runs the given function, returning the message of any panic which unwinds out of it.
+///
+/// Settings extensions run user-provided model code, which must not be allowed to abort the
+/// extension without producing output that adheres to the CLI protocol.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic payload is not a string".to_string())
+    })
+}
+
 impl<Mi, Mo> std::fmt::Debug for SettingsExtension<Mi, Mo>
 where
     Mo: AsTypeErasedModel,
@@ -184,6 +348,12 @@ where
         f.debug_struct("SettingsExtension")
             .field("name", &self.name)
             .field("model-versions", &self.models.keys().collect::<Vec<_>>())
+            .field(
+                "template-helpers",
+                &self.template_helpers.keys().collect::<Vec<_>>(),
+            )
+            .field("timeouts", &self.timeouts)
+            .field("hooks", &self.hooks.len())
             .field("migrator", &self.migrator)
             .finish()
     }
@@ -210,9 +380,21 @@ pub mod error {
     where
         MigratorError: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
     {
+        #[snafu(display("Failed to apply JSON patch to current value: {}", source))]
+        ApplyJsonPatch { source: json_patch::PatchError },
+
+        #[snafu(display("Diff operation failed: {}", source))]
+        Diff { source: BottlerocketSettingError },
+
         #[snafu(display("Generate operation failed: {}", source))]
         Generate { source: BottlerocketSettingError },
 
+        #[snafu(display("{} operation rejected by hook: {}", operation, source))]
+        Hook {
+            operation: &'static str,
+            source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        },
+
         #[snafu(display("Migrate operation failed: {}", source))]
         Migrate {
             #[snafu(source(from(MigratorError, Into::into)))]
@@ -241,12 +423,30 @@ pub mod error {
         #[snafu(display("Requested model version '{}' not found", setting_version))]
         NoSuchModel { setting_version: String },
 
+        #[snafu(display("{} operation panicked: {}", operation, message))]
+        Panic {
+            operation: &'static str,
+            message: String,
+        },
+
         #[snafu(display("Failed to parse CLI arguments: No CLI command given"))]
         ParseCLICommand,
 
         #[snafu(display("Failed to parse CLI arguments: {}", parser_output))]
         ParseCLIArgs { parser_output: String },
 
+        #[snafu(display("Failed to parse JSON patch: {}", source))]
+        ParseJsonPatch { source: serde_json::Error },
+
+        #[snafu(display("Failed to parse preserved migration data: {}", source))]
+        PreservedDataParse { source: serde_json::Error },
+
+        #[cfg(feature = "render")]
+        #[snafu(display("Render operation failed: {}", source))]
+        Render {
+            source: crate::render::TemplateRenderError,
+        },
+
         #[snafu(display("Failed to write settings extension output as JSON: {}", source))]
         SerializeResult { source: serde_json::Error },
 
@@ -256,6 +456,18 @@ pub mod error {
         #[snafu(display("Template helper execution failed: {}", source))]
         TemplateHelper { source: BottlerocketSettingError },
 
+        #[snafu(display("{} operation timed out after {:?}", operation, timeout))]
+        Timeout {
+            operation: &'static str,
+            timeout: std::time::Duration,
+        },
+
+        #[snafu(display("Cannot limit the time of unknown command '{}'", command))]
+        UnknownTimeoutCommand { command: String },
+
+        #[snafu(display("Unset operation failed: {}", source))]
+        Unset { source: BottlerocketSettingError },
+
         #[snafu(display("Validate operation failed: {}", source))]
         Validate { source: BottlerocketSettingError },
 
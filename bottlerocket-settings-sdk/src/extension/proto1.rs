@@ -2,25 +2,61 @@
 //!
 //! The protocol is provided as a trait so that any new protocols can provide implementations
 //! with function name collisions if needed.
-use super::{error, SettingsExtensionError};
+use super::watchdog::Watchdog;
+use super::{catch_panic, error, CommandHook, SettingsExtensionError};
+#[cfg(feature = "render")]
+use crate::cli::proto1::RenderCommand;
 use crate::cli::proto1::{
-    FloodMigrateCommand, GenerateCommand, MigrateCommand, Proto1Command, SetCommand,
-    TemplateHelperCommand, ValidateCommand,
+    DiffCommand, FloodMigrateCommand, GenerateCommand, ListHelpersCommand, MigrateCommand,
+    Proto1Command, SetCommand, TemplateHelperCommand, UnsetCommand, ValidateCommand,
 };
+#[cfg(feature = "render")]
+use crate::helper::HelperContext;
+use crate::helper::HelperSignature;
 use crate::migrate::Migrator;
 use crate::model::erased::AsTypeErasedModel;
 use crate::SettingsExtension;
 use snafu::{OptionExt, ResultExt};
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::instrument;
 
 /// Runs a proto1 command against the given settings extension.
 ///
 /// Results are printed to stdout/stderr, adhering to Bottlerocket settings extension CLI proto1.
 /// Once the extension has run, the program terminates.
+///
+/// If the extension sets a time limit for the command, a [`Watchdog`] reports a
+/// [`SettingsExtensionError::Timeout`] and terminates the process once the limit is exceeded. The
+/// extension's [`CommandHook`]s are called after the timeout with its error before the process
+/// exits.
 pub fn run_extension<P: Proto1>(extension: P, cmd: Proto1Command) -> ExitCode {
-    match try_run_extension(extension, cmd) {
+    let operation = cmd.name();
+    let watchdog = extension.timeout(&cmd).map(|timeout| {
+        let hooks = extension.command_hooks().to_vec();
+        let cmd = cmd.clone();
+        Watchdog::start(timeout, move || {
+            let timeout_error: SettingsExtensionError<P::MigratorErrorKind> =
+                error::TimeoutSnafu { operation, timeout }.build();
+            // The process exits regardless, so hook panics are not reported.
+            call_after_hooks(&hooks, &cmd, Err(&timeout_error));
+            println!("{}", timeout_error);
+            std::process::exit(1);
+        })
+    });
+
+    // The watchdog is stopped once the command has run, so that hooks are never called after both
+    // the command and its timeout.
+    let result = run_with_hooks(&extension, cmd, || {
+        if let Some(watchdog) = watchdog {
+            watchdog.finish();
+        }
+    });
+
+    match result {
         Ok(output) => {
             println!("{}", &output);
             ExitCode::SUCCESS
@@ -34,12 +70,86 @@ pub fn run_extension<P: Proto1>(extension: P, cmd: Proto1Command) -> ExitCode {
 
 /// Runs a proto1 command against the given settings extension.
 ///
-/// The results are returned to the caller.
-#[tracing::instrument(err)]
+/// The results are returned to the caller. Panics raised while running the command are returned as
+/// [`SettingsExtensionError::Panic`].
+///
+/// The extension's [`CommandHook`]s are called before and after the command runs. Panics raised by
+/// hooks are also returned as [`SettingsExtensionError::Panic`].
 pub fn try_run_extension<P, ME>(
     extension: P,
     cmd: Proto1Command,
 ) -> Result<String, SettingsExtensionError<ME>>
+where
+    P: Proto1<MigratorErrorKind = ME>,
+    ME: std::error::Error + Send + Sync + 'static,
+{
+    run_with_hooks(&extension, cmd, || ())
+}
+
+/// Runs a proto1 command against the given settings extension, calling its hooks before and after
+/// the command. `after_command` is called once the command has run, before the after hooks.
+// Command arguments may contain sensitive values, so only the command's name is recorded.
+#[tracing::instrument(skip(cmd, after_command), fields(command = cmd.name()), err)]
+fn run_with_hooks<P, ME>(
+    extension: &P,
+    cmd: Proto1Command,
+    after_command: impl FnOnce(),
+) -> Result<String, SettingsExtensionError<ME>>
+where
+    P: Proto1<MigratorErrorKind = ME>,
+    ME: std::error::Error + Send + Sync + 'static,
+{
+    let operation = cmd.name();
+    let hooks = extension.command_hooks();
+    let hook_panic = |message| error::PanicSnafu {
+        operation,
+        message: format!("in command hook: {}", message),
+    };
+
+    let result = hooks
+        .iter()
+        .try_for_each(|hook| {
+            catch_panic(|| hook.before(&cmd))
+                .map_err(|message| hook_panic(message).build())?
+                .context(error::HookSnafu { operation })
+        })
+        .and_then(|_| run_command(extension, cmd.clone()));
+    after_command();
+
+    let after_panic = call_after_hooks(
+        hooks,
+        &cmd,
+        result
+            .as_ref()
+            .map(String::as_str)
+            .map_err(|e| e as &dyn std::error::Error),
+    );
+
+    match after_panic {
+        Some(message) => hook_panic(message).fail(),
+        None => result,
+    }
+}
+
+/// Calls every hook after a command, even if an earlier one panics, returning the message of the
+/// first panic.
+fn call_after_hooks(
+    hooks: &[Arc<dyn CommandHook>],
+    cmd: &Proto1Command,
+    result: Result<&str, &dyn std::error::Error>,
+) -> Option<String> {
+    let panics: Vec<String> = hooks
+        .iter()
+        .filter_map(|hook| catch_panic(|| hook.after(cmd, result)).err())
+        .collect();
+    panics.into_iter().next()
+}
+
+/// Runs a proto1 command against the given settings extension, catching any panics.
+fn run_command<P, ME>(
+    extension: &P,
+    cmd: Proto1Command,
+) -> Result<String, SettingsExtensionError<ME>>
 where
     P: Proto1<MigratorErrorKind = ME>,
     ME: std::error::Error + Send + Sync + 'static,
@@ -47,14 +157,21 @@ where
     let json_stringify =
         |value| serde_json::to_string_pretty(&value).context(error::SerializeResultSnafu);
 
-    match cmd {
+    let operation = cmd.name();
+    catch_panic(|| match cmd {
         Proto1Command::Set(s) => extension.set(s).map(|_| String::new()),
+        Proto1Command::Unset(u) => extension.unset(u).and_then(json_stringify),
+        Proto1Command::Diff(d) => extension.diff(d).and_then(json_stringify),
         Proto1Command::Generate(g) => extension.generate(g).and_then(json_stringify),
         Proto1Command::Migrate(m) => extension.migrate(m).and_then(json_stringify),
         Proto1Command::FloodMigrate(m) => extension.flood_migrate(m).and_then(json_stringify),
         Proto1Command::Validate(v) => extension.validate(v).map(|_| String::new()),
         Proto1Command::Helper(h) => extension.template_helper(h).and_then(json_stringify),
-    }
+        Proto1Command::Helpers(h) => extension.list_helpers(h).and_then(json_stringify),
+        #[cfg(feature = "render")]
+        Proto1Command::Render(r) => extension.render(r),
+    })
+    .unwrap_or_else(|message| error::PanicSnafu { operation, message }.fail())
 }
 
 /// A trait representing adherence to Bottlerocket settings extension CLI proto1.
@@ -63,7 +180,25 @@ where
 pub trait Proto1: Debug {
     type MigratorErrorKind: std::error::Error + Send + Sync + 'static;
 
+    /// Returns the hooks which are called around each command.
+    fn command_hooks(&self) -> &[Arc<dyn CommandHook>] {
+        &[]
+    }
+
+    /// Returns the time limit for running the given command, if any.
+    fn timeout(&self, _command: &Proto1Command) -> Option<Duration> {
+        None
+    }
+
     fn set(&self, args: SetCommand) -> Result<(), SettingsExtensionError<Self::MigratorErrorKind>>;
+    fn unset(
+        &self,
+        args: UnsetCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>>;
+    fn diff(
+        &self,
+        args: DiffCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>>;
     fn generate(
         &self,
         args: GenerateCommand,
@@ -84,6 +219,15 @@ pub trait Proto1: Debug {
         &self,
         args: TemplateHelperCommand,
     ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>>;
+    fn list_helpers(
+        &self,
+        args: ListHelpersCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>>;
+    #[cfg(feature = "render")]
+    fn render(
+        &self,
+        args: RenderCommand,
+    ) -> Result<String, SettingsExtensionError<Self::MigratorErrorKind>>;
 }
 
 impl<Mi, Mo> Proto1 for SettingsExtension<Mi, Mo>
@@ -93,18 +237,69 @@ where
 {
     type MigratorErrorKind = Mi::ErrorKind;
 
-    #[instrument(err)]
+    fn command_hooks(&self) -> &[Arc<dyn CommandHook>] {
+        &self.hooks
+    }
+
+    fn timeout(&self, command: &Proto1Command) -> Option<Duration> {
+        self.timeouts.get(command.name())
+    }
+
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
     fn set(&self, args: SetCommand) -> Result<(), SettingsExtensionError<Self::MigratorErrorKind>> {
+        let version = args
+            .target_version
+            .as_ref()
+            .unwrap_or(&args.setting_version);
+        let model = self.model(version).context(error::NoSuchModelSnafu {
+            setting_version: version.clone(),
+        })?;
+
+        let target = migrate_input(self, set_target(&args)?, &args.setting_version, version)?;
+        let current_value = args
+            .current_value
+            .map(|current_value| migrate_input(self, current_value, &args.setting_version, version))
+            .transpose()?;
+
+        model
+            .as_model()
+            .set(current_value, target)
+            .context(error::SetSnafu)
+    }
+
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
+    fn unset(
+        &self,
+        args: UnsetCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>> {
         self.model(&args.setting_version)
             .context(error::NoSuchModelSnafu {
                 setting_version: args.setting_version,
             })?
             .as_model()
-            .set(args.current_value, args.value)
-            .context(error::SetSnafu)
+            .unset(args.current_value)
+            .context(error::UnsetSnafu)
+            .and_then(|unset_result| {
+                serde_json::to_value(unset_result).context(error::SerializeResultSnafu)
+            })
     }
 
-    #[instrument(err)]
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
+    fn diff(
+        &self,
+        args: DiffCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>> {
+        self.model(&args.setting_version)
+            .context(error::NoSuchModelSnafu {
+                setting_version: args.setting_version,
+            })?
+            .as_model()
+            .diff(args.current_value, args.value)
+            .context(error::DiffSnafu)
+            .and_then(|diff| serde_json::to_value(diff).context(error::SerializeResultSnafu))
+    }
+
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
     fn generate(
         &self,
         args: GenerateCommand,
@@ -121,7 +316,7 @@ where
             })
     }
 
-    #[instrument(err)]
+    #[instrument(skip(args), fields(from_version = %args.from_version, target_version = %args.target_version), err)]
     fn migrate(
         &self,
         args: MigrateCommand,
@@ -140,17 +335,46 @@ where
                     setting_version: args.from_version.clone(),
                 })?;
 
-        self.migrator
-            .perform_migration(
-                self,
-                starting_value,
-                &args.from_version,
-                &args.target_version,
-            )
-            .context(error::MigrateSnafu)
+        if !args.preserve && args.preserved_data.is_none() {
+            return catch_migration_panic(|| {
+                self.migrator
+                    .perform_migration(
+                        self,
+                        starting_value,
+                        &args.from_version,
+                        &args.target_version,
+                    )
+                    .context(error::MigrateSnafu)
+            });
+        }
+
+        let preserved_data = args
+            .preserved_data
+            .map(serde_json::from_value)
+            .transpose()
+            .context(error::PreservedDataParseSnafu)?
+            .unwrap_or_default();
+
+        let result = catch_migration_panic(|| {
+            self.migrator
+                .perform_preserving_migration(
+                    self,
+                    starting_value,
+                    &args.from_version,
+                    &args.target_version,
+                    preserved_data,
+                )
+                .context(error::MigrateSnafu)
+        })?;
+
+        if args.preserve {
+            serde_json::to_value(result).context(error::SerializeResultSnafu)
+        } else {
+            Ok(result.value)
+        }
     }
 
-    #[instrument(err)]
+    #[instrument(skip(args), fields(from_version = %args.from_version), err)]
     fn flood_migrate(
         &self,
         args: FloodMigrateCommand,
@@ -169,23 +393,32 @@ where
                     setting_version: args.from_version.clone(),
                 })?;
 
-        self.migrator
-            .perform_flood_migrations(self, starting_value, &args.from_version)
-            .context(error::MigrateSnafu)
-            .and_then(|value| serde_json::to_value(value).context(error::SerializeResultSnafu))
+        catch_migration_panic(|| {
+            self.migrator
+                .perform_flood_migrations(self, starting_value, &args.from_version)
+                .context(error::MigrateSnafu)
+        })
+        .and_then(|value| serde_json::to_value(value).context(error::SerializeResultSnafu))
     }
 
-    #[instrument(err)]
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
     fn validate(
         &self,
         args: ValidateCommand,
     ) -> Result<(), SettingsExtensionError<Self::MigratorErrorKind>> {
-        self.model(&args.setting_version)
-            .context(error::NoSuchModelSnafu {
-                setting_version: args.setting_version,
-            })?
+        let version = args
+            .target_version
+            .as_ref()
+            .unwrap_or(&args.setting_version);
+        let model = self.model(version).context(error::NoSuchModelSnafu {
+            setting_version: version.clone(),
+        })?;
+
+        let value = migrate_input(self, args.value, &args.setting_version, version)?;
+
+        model
             .as_model()
-            .validate(args.value, args.required_settings)
+            .validate(value, args.required_settings)
             .context(error::ValidateSnafu)
     }
 
@@ -193,12 +426,125 @@ where
         &self,
         args: TemplateHelperCommand,
     ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>> {
-        self.model(&args.setting_version)
-            .context(error::NoSuchModelSnafu {
-                setting_version: args.setting_version,
-            })?
-            .as_model()
-            .execute_template_helper(&args.helper_name, args.arg)
-            .context(error::TemplateHelperSnafu)
+        let invocation = args.invocation();
+        self.execute_template_helper(&args.setting_version, &args.helper_name, invocation)
+    }
+
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
+    fn list_helpers(
+        &self,
+        args: ListHelpersCommand,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Self::MigratorErrorKind>> {
+        let signatures: BTreeMap<String, Option<HelperSignature>> = self
+            .template_helpers(&args.setting_version)?
+            .iter()
+            .map(|(name, helper)| (name.clone(), helper.signature()))
+            .collect();
+
+        serde_json::to_value(signatures).context(error::SerializeResultSnafu)
+    }
+
+    #[cfg(feature = "render")]
+    #[instrument(skip(args), fields(setting_version = %args.setting_version), err)]
+    fn render(
+        &self,
+        args: RenderCommand,
+    ) -> Result<String, SettingsExtensionError<Self::MigratorErrorKind>> {
+        let helpers = self.template_helpers(&args.setting_version)?;
+
+        let mut helper_context =
+            HelperContext::new(&args.setting_version).with_value(args.value.clone());
+        helper_context.dependent_settings = args.required_settings.clone();
+
+        let context =
+            crate::render::settings_context(self.name, args.value, args.required_settings)
+                .context(error::RenderSnafu)?;
+        crate::render::render_template(&args.template, &context, helpers, Some(helper_context))
+            .context(error::RenderSnafu)
+    }
+}
+
+/// Migrates a value given to a command in `from_version` to `target_version`, the version in which
+/// it is stored.
+///
+/// Values which are already in the target version are returned as they are.
+fn migrate_input<Mi, Mo>(
+    extension: &SettingsExtension<Mi, Mo>,
+    value: serde_json::Value,
+    from_version: &str,
+    target_version: &str,
+) -> Result<serde_json::Value, SettingsExtensionError<Mi::ErrorKind>>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    if from_version == target_version {
+        return Ok(value);
+    }
+
+    let model = extension
+        .model(from_version)
+        .context(error::NoSuchModelSnafu {
+            setting_version: from_version,
+        })?;
+
+    let starting_value = model
+        .as_model()
+        .parse_erased(value)
+        .context(error::ModelParseSnafu {
+            setting_version: from_version,
+        })?;
+
+    catch_migration_panic(|| {
+        extension
+            .migrator
+            .perform_migration(extension, starting_value, from_version, target_version)
+            .context(error::MigrateSnafu)
+    })
+}
+
+/// Runs a migration, reporting any panic raised while migrating as a panic of the `migrate`
+/// operation, whichever command required the migration.
+fn catch_migration_panic<T, ME>(
+    migrate: impl FnOnce() -> Result<T, SettingsExtensionError<ME>>,
+) -> Result<T, SettingsExtensionError<ME>>
+where
+    ME: std::error::Error + Send + Sync + 'static,
+{
+    catch_panic(migrate).unwrap_or_else(|message| {
+        error::PanicSnafu {
+            operation: "migrate",
+            message,
+        }
+        .fail()
+    })
+}
+
+/// Returns the complete value requested by a `set` command, applying any patch given in its place
+/// to the current value.
+///
+/// A missing current value is patched as `null`.
+fn set_target<ME>(args: &SetCommand) -> Result<serde_json::Value, SettingsExtensionError<ME>>
+where
+    ME: std::error::Error + Send + Sync + 'static,
+{
+    let mut target = args.current_value.clone().unwrap_or_default();
+
+    match (&args.value, &args.merge_patch, &args.json_patch) {
+        (Some(value), None, None) => Ok(value.clone()),
+        (None, Some(merge_patch), None) => {
+            json_patch::merge(&mut target, merge_patch);
+            Ok(target)
+        }
+        (None, None, Some(patch)) => {
+            let patch: json_patch::Patch =
+                serde_json::from_value(patch.clone()).context(error::ParseJsonPatchSnafu)?;
+            json_patch::patch(&mut target, &patch).context(error::ApplyJsonPatchSnafu)?;
+            Ok(target)
+        }
+        _ => error::ParseCLIArgsSnafu {
+            parser_output: "set requires exactly one of --value, --merge-patch or --json-patch",
+        }
+        .fail(),
     }
 }
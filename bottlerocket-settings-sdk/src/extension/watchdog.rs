@@ -0,0 +1,64 @@
+//! Provides time limits for settings extension commands.
+//!
+//! Model code runs on the extension's main thread, so a command which exceeds its time limit cannot
+//! be interrupted. Instead, a [`Watchdog`] thread reports the timeout using the CLI protocol and
+//! terminates the extension.
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// The time limits configured for an extension's commands.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Timeouts {
+    pub(crate) default: Option<Duration>,
+    pub(crate) commands: HashMap<String, Duration>,
+}
+
+impl Timeouts {
+    /// Returns the time limit for the command with the given name, if any.
+    pub(crate) fn get(&self, command: &str) -> Option<Duration> {
+        self.commands.get(command).copied().or(self.default)
+    }
+}
+
+/// Calls a function from a separate thread if not finished within a time limit.
+pub(crate) struct Watchdog {
+    finished: Arc<Mutex<bool>>,
+    cancel: mpsc::Sender<()>,
+}
+
+impl Watchdog {
+    /// Starts a watchdog which calls `on_timeout` unless [`Watchdog::finish`] is called within
+    /// `timeout`.
+    ///
+    /// `on_timeout` is expected to terminate the process. Until it returns, calls to
+    /// [`Watchdog::finish`] block.
+    pub(crate) fn start<F>(timeout: Duration, on_timeout: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let finished = Arc::new(Mutex::new(false));
+        let (cancel, cancelled) = mpsc::channel();
+
+        let watched = Arc::clone(&finished);
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = cancelled.recv_timeout(timeout) {
+                let finished = watched.lock().unwrap_or_else(PoisonError::into_inner);
+                if !*finished {
+                    on_timeout();
+                }
+            }
+        });
+
+        Self { finished, cancel }
+    }
+
+    /// Stops the watchdog, ensuring that it will not call its timeout function.
+    pub(crate) fn finish(self) {
+        *self.finished.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        // The watchdog thread may have already exited, in which case there is nothing to cancel.
+        let _ = self.cancel.send(());
+    }
+}
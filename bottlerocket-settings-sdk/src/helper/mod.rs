@@ -3,6 +3,14 @@
 //! See the documentation of [`HelperDef`] for more information.
 pub use bottlerocket_template_helper::template_helper;
 
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use std::collections::BTreeMap;
+
+mod signature;
+pub use signature::{HelperParam, HelperSignature, HelperType, JsonType};
+
 /// This trait allows users to create custom helper functions for use in Bottlerocket's templating
 /// configuration system.
 ///
@@ -10,11 +18,19 @@ pub use bottlerocket_template_helper::template_helper;
 ///
 /// # Helper Definitions
 /// Any type that implements [`HelperDef`] can be used as a helper. You can use the
-/// [`template_helper`] annotation to generate a function that implements [`HelperDef`] for you, so
+/// [`template_helper`] annotation to generate a helper that implements [`HelperDef`] for you, so
 /// long as:
-/// * Your function arguments implement [`serde::Deserialize`]
-/// * Your return value is a `Result<T, E>` where `T` implements [`serde::Serialize`]
-///   and `E` implements `Into<Box<dyn std::error::Error>>`.
+/// * Your function arguments implement [`serde::Deserialize`], or are references to such types
+/// * Your return value implements [`serde::Serialize`], or is a `Result<T, E>` where `T`
+///   implements [`serde::Serialize`] and `E` implements `Into<Box<dyn std::error::Error>>`.
+///
+/// The generated helper is a unit struct which can be called like a function with a list of JSON
+/// values. It also accepts named arguments and the helper's context, and describes the helper's
+/// parameters and return value via [`HelperDef::signature`].
+///
+/// Helpers which need state, such as precomputed tables or loaded configuration, can be written as
+/// a method on a struct; see [`template_helper`] for details. Closures taking a list of JSON values
+/// are also helpers.
 ///
 /// # Example
 ///
@@ -28,10 +44,12 @@ pub use bottlerocket_template_helper::template_helper;
 /// }
 ///
 /// assert_eq!(
-///     join_strings_helper.helper_fn(vec![json!("hello "), json!("world")]).unwrap(),
+///     join_strings_helper(vec![json!("hello "), json!("world")]).unwrap(),
 ///     json!("hello world")
 /// );
 ///
+/// let signature = join_strings_helper.signature().unwrap();
+/// assert_eq!(signature.params[0].name, "lhs");
 /// ```
 pub trait HelperDef {
     /// Executes the helper.
@@ -39,23 +57,186 @@ pub trait HelperDef {
     /// All inputs are provided as a list of JSON values, and a resulting JSON value is expected as
     /// output.
     fn helper_fn(&self, args: Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError>;
+
+    /// Executes the helper with both positional and named (hash) arguments.
+    ///
+    /// By default, helpers do not accept named arguments, and calls that include them fail.
+    fn execute(&self, invocation: HelperInvocation) -> Result<serde_json::Value, HelperError> {
+        if let Some(name) = invocation.hash.keys().next() {
+            return error::UnexpectedKeywordSnafu { name }.fail();
+        }
+        self.helper_fn(invocation.args)
+    }
+
+    /// Describes the helper's parameters and return value, if known.
+    fn signature(&self) -> Option<HelperSignature> {
+        None
+    }
 }
 
-impl<F: Fn(Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError>> HelperDef for F {
+impl<F> HelperDef for F
+where
+    F: Fn(Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError>,
+{
     fn helper_fn(&self, args: Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError> {
         self(args)
     }
 }
 
+/// The arguments that a template helper is called with.
+///
+/// In a template, a call like `{{join items sep=","}}` passes `items` as a positional argument and
+/// `sep` as a named (hash) argument.
+///
+/// ```
+/// use bottlerocket_settings_sdk::helper::HelperInvocation;
+/// use serde_json::json;
+///
+/// let invocation = HelperInvocation::new(vec![json!(["a", "b"])]).with_hash_arg("sep", json!(","));
+/// assert_eq!(invocation.hash["sep"], json!(","));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperInvocation {
+    /// The positional arguments, in order.
+    pub args: Vec<serde_json::Value>,
+    /// The named arguments.
+    pub hash: BTreeMap<String, serde_json::Value>,
+    /// The setting the helper is being called for, if known.
+    pub context: Option<HelperContext>,
+}
+
+impl HelperInvocation {
+    /// Creates an invocation with the given positional arguments and no named arguments.
+    pub fn new(args: Vec<serde_json::Value>) -> Self {
+        Self {
+            args,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a named argument to the invocation.
+    pub fn with_hash_arg(mut self, name: &str, value: serde_json::Value) -> Self {
+        self.hash.insert(name.to_string(), value);
+        self
+    }
+
+    /// Sets the context of the setting which the helper is being called for.
+    pub fn with_context(mut self, context: HelperContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Removes and returns the named argument with the given name, failing if it was not given.
+    ///
+    /// This is used by helpers generated with [`template_helper`].
+    pub fn take_keyword(&mut self, name: &str) -> Result<serde_json::Value, HelperError> {
+        self.hash
+            .remove(name)
+            .context(error::MissingKeywordSnafu { name })
+    }
+
+    /// Returns the context of the setting the helper is called for, failing if it was not given.
+    ///
+    /// This is used by helpers generated with [`template_helper`].
+    pub fn require_context(&self) -> Result<HelperContext, HelperError> {
+        self.context.clone().context(error::MissingContextSnafu)
+    }
+
+    /// Fails if the invocation contains any named argument not in the given list.
+    ///
+    /// This is used by helpers generated with [`template_helper`].
+    pub fn check_keywords(&self, keywords: &[&str]) -> Result<(), HelperError> {
+        match self
+            .hash
+            .keys()
+            .find(|name| !keywords.contains(&name.as_str()))
+        {
+            Some(name) => error::UnexpectedKeywordSnafu { name }.fail(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The setting that a template helper is being called for.
+///
+/// Helpers generated by [`template_helper`] can receive the context by marking a parameter of type
+/// `HelperContext` with `#[helper_context]`. The context is not passed as an argument from
+/// templates. Calls which give no context fail, unless the parameter is an
+/// `Option<HelperContext>`.
+///
+/// ```
+/// use bottlerocket_settings_sdk::helper::{HelperContext, HelperDef, HelperInvocation, template_helper};
+/// use serde_json::json;
+///
+/// #[template_helper(ident = greeting_helper)]
+/// fn greeting(#[helper_context] context: HelperContext) -> Result<String, anyhow::Error> {
+///     let motd: Option<String> = context.parse_value()?;
+///     Ok(motd.unwrap_or_default())
+/// }
+///
+/// let invocation = HelperInvocation::new(vec![])
+///     .with_context(HelperContext::new("v1").with_value(json!("Hello!")));
+/// assert_eq!(greeting_helper.execute(invocation).unwrap(), json!("Hello!"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperContext {
+    /// The version of the setting's model which provides the helper.
+    pub version: String,
+    /// The current value of the setting, if known.
+    pub value: Option<serde_json::Value>,
+    /// The settings that this setting depends on, as a JSON object keyed by setting name, if known.
+    pub dependent_settings: Option<serde_json::Value>,
+}
+
+impl HelperContext {
+    /// Creates a context for the given model version, with no setting values.
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the current value of the setting.
+    pub fn with_value(mut self, value: serde_json::Value) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Sets the settings that this setting depends on.
+    pub fn with_dependent_settings(mut self, dependent_settings: serde_json::Value) -> Self {
+        self.dependent_settings = Some(dependent_settings);
+        self
+    }
+
+    /// Parses the current value of the setting, if known, into the given type.
+    pub fn parse_value<T: DeserializeOwned>(&self) -> Result<Option<T>, HelperError> {
+        self.value
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .context(error::JSONParseSnafu)
+    }
+}
+
+impl From<Vec<serde_json::Value>> for HelperInvocation {
+    fn from(args: Vec<serde_json::Value>) -> Self {
+        Self::new(args)
+    }
+}
+
 #[macro_export]
 /// Creates a map of helper names to helper definitions.
 ///
-/// This macro is useful for providing template helpers from a settings model:
+/// This macro is useful for providing template helpers from a settings model. Each helper may be
+/// any expression whose value implements [`HelperDef`], such as a helper generated by
+/// [`template_helper`], an instance of a stateful helper, or a closure:
 ///
 /// ```
 /// # use std::collections::HashMap;
 /// use bottlerocket_settings_sdk::{
-///     HelperDef, provide_template_helpers, template_helper};
+///     HelperDef, HelperError, provide_template_helpers, template_helper};
+/// use serde_json::Value;
 ///
 /// #[template_helper(ident = exclaim_helper)]
 /// fn exclaim(s: String) -> Result<String, anyhow::Error> {
@@ -63,19 +244,24 @@ impl<F: Fn(Vec<serde_json::Value>) -> Result<serde_json::Value, HelperError>> He
 /// }
 ///
 /// fn template_helpers() -> HashMap<String, Box<dyn HelperDef>> {
+///     let greeting = "Hello".to_string();
+///
 ///     provide_template_helpers! {
 ///         "exclaim" => exclaim_helper,
+///         "greeting" => move |_: Vec<Value>| -> Result<Value, HelperError> {
+///             Ok(Value::String(greeting.clone()))
+///         },
 ///     }
 /// }
 /// ```
 macro_rules! provide_template_helpers {
-    ($($helper_name:expr => $helper:ident),* $(,)?) => {
+    ($($helper_name:expr => $helper:expr),* $(,)?) => {
         {
             let mut helpers = std::collections::HashMap::new();
             $(
                 helpers.insert(
                     $helper_name.to_string(),
-                    Box::new($helper)as Box<dyn bottlerocket_settings_sdk::HelperDef>
+                    Box::new($helper) as Box<dyn bottlerocket_settings_sdk::HelperDef>
                 );
             )*
             helpers
@@ -85,6 +271,7 @@ macro_rules! provide_template_helpers {
 
 mod error {
     #![allow(missing_docs)]
+    use super::describe_arity;
     use snafu::Snafu;
 
     /// Error type used in helper definitions.
@@ -93,11 +280,12 @@ mod error {
     pub enum HelperError {
         #[snafu(display(
             "Helper called with incorrect arity: expected {} args, but {} provided",
-            expected_args,
+            describe_arity(*min_args, *max_args),
             provided_args
         ))]
         Arity {
-            expected_args: usize,
+            min_args: usize,
+            max_args: Option<usize>,
             provided_args: usize,
         },
 
@@ -111,6 +299,26 @@ mod error {
 
         #[snafu(display("Failed to parse outgoing value to JSON: {}", source))]
         JSONSerialize { source: serde_json::Error },
+
+        #[snafu(display(
+            "Helper requires the context of the setting it is called for, but none was given"
+        ))]
+        MissingContext,
+
+        #[snafu(display("Helper called without required named argument '{}'", name))]
+        MissingKeyword { name: String },
+
+        #[snafu(display("Helper called with unexpected named argument '{}'", name))]
+        UnexpectedKeyword { name: String },
     }
 }
 pub use error::HelperError;
+
+/// Describes the number of arguments a helper accepts, e.g. "2", "1 to 3", or "at least 1".
+fn describe_arity(min_args: usize, max_args: Option<usize>) -> String {
+    match max_args {
+        Some(max_args) if max_args == min_args => min_args.to_string(),
+        Some(max_args) => format!("{} to {}", min_args, max_args),
+        None => format!("at least {}", min_args),
+    }
+}
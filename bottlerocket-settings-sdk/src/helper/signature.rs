@@ -0,0 +1,123 @@
+//! Describes the parameters and return values of template helpers.
+//!
+//! Signatures are generated by the [`template_helper`](super::template_helper) macro, and allow
+//! tools to check calls to helpers without executing them.
+use serde::{Deserialize, Serialize};
+
+/// A description of a template helper's parameters and return value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperSignature {
+    /// The name of the function which implements the helper.
+    pub name: String,
+    /// The helper's parameters, in the order that they are passed.
+    pub params: Vec<HelperParam>,
+    /// The value returned by the helper.
+    pub returns: HelperType,
+    /// The documentation of the function which implements the helper, if any.
+    pub doc: Option<String>,
+    /// Whether the helper receives the [`HelperContext`](super::HelperContext) of the setting it
+    /// is called for.
+    pub uses_context: bool,
+}
+
+impl HelperSignature {
+    /// Returns the minimum number of positional arguments the helper accepts.
+    pub fn min_args(&self) -> usize {
+        self.positional()
+            .filter(|param| !param.optional && !param.variadic)
+            .count()
+    }
+
+    /// Returns the maximum number of positional arguments the helper accepts, or `None` if the
+    /// helper is variadic.
+    pub fn max_args(&self) -> Option<usize> {
+        (!self.positional().any(|param| param.variadic)).then_some(self.positional().count())
+    }
+
+    /// Returns the parameters which are passed as named (hash) arguments.
+    pub fn keywords(&self) -> impl Iterator<Item = &HelperParam> {
+        self.params.iter().filter(|param| param.keyword)
+    }
+
+    /// Returns the parameters which are passed as positional arguments.
+    pub fn positional(&self) -> impl Iterator<Item = &HelperParam> {
+        self.params.iter().filter(|param| !param.keyword)
+    }
+
+    /// Returns whether the helper accepts the given number of positional arguments.
+    pub fn accepts_arity(&self, num_args: usize) -> bool {
+        num_args >= self.min_args() && self.max_args().is_none_or(|max| num_args <= max)
+    }
+}
+
+/// A single parameter accepted by a template helper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperParam {
+    /// The name of the parameter.
+    pub name: String,
+    /// The type of the parameter.
+    #[serde(rename = "type")]
+    pub ty: HelperType,
+    /// Whether the parameter may be omitted. Omitted parameters are passed as `null`.
+    pub optional: bool,
+    /// Whether the parameter collects all remaining arguments into an array.
+    pub variadic: bool,
+    /// Whether the parameter is passed as a named (hash) argument, rather than positionally.
+    pub keyword: bool,
+}
+
+/// The type of a value passed to or returned by a template helper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperType {
+    /// The Rust type, as written in the helper's definition.
+    pub rust_type: String,
+    /// The JSON type which the Rust type is (de)serialized as.
+    pub json_type: JsonType,
+}
+
+/// The JSON type of a value passed to or returned by a template helper.
+///
+/// Types which cannot be determined from the helper's definition, such as user-defined structs,
+/// are described as [`JsonType::Any`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "items", rename_all = "lowercase")]
+pub enum JsonType {
+    /// Any JSON value.
+    Any,
+    /// An array whose elements have the given type.
+    Array(Box<JsonType>),
+    /// A boolean.
+    Boolean,
+    /// `null`.
+    Null,
+    /// A value of the given type, or `null`.
+    Nullable(Box<JsonType>),
+    /// A number.
+    Number,
+    /// An object whose values have the given type.
+    Object(Box<JsonType>),
+    /// A string.
+    String,
+}
+
+impl JsonType {
+    /// Returns whether a JSON value is compatible with this type.
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        match (self, value) {
+            (Self::Any, _) => true,
+            (Self::Array(items), Value::Array(values)) => values.iter().all(|v| items.matches(v)),
+            (Self::Boolean, Value::Bool(_)) => true,
+            (Self::Null, Value::Null) => true,
+            (Self::Nullable(_), Value::Null) => true,
+            (Self::Nullable(inner), value) => inner.matches(value),
+            (Self::Number, Value::Number(_)) => true,
+            (Self::Object(items), Value::Object(values)) => {
+                values.values().all(|v| items.matches(v))
+            }
+            (Self::String, Value::String(_)) => true,
+            _ => false,
+        }
+    }
+}
@@ -17,15 +17,51 @@ tool wishes to invoke a settings extension and parse the output.
 * **proto1** -
   When enabled, this allows extensions built against the SDK to serve the Settings Extension CLI
   protocol version "proto1".
+
+* **client** -
+  When enabled, this exposes the `client` module, which provides an `ExtensionClient` for invoking
+  settings extensions as subprocesses using CLI protocol "proto1". This does not require the
+  **extension** feature.
+
+* **testing** -
+  When enabled, this exposes the `testing` module, which provides utilities for testing settings
+  extensions: a CLI-level test harness, JSON fixture loading, and checks that migrations round-trip
+  values between versions.
+
+* **render** -
+  When enabled, this exposes the `render` module and the proto1 `render` command, which render
+  Handlebars config templates using a settings model's template helpers.
+
+* **simulator** -
+  When enabled, this exposes the `simulator` module, which runs repeated `generate` cycles across
+  several settings extensions locally, as well as the `settings-generation-simulator` binary which
+  does the same for settings extension binaries.
+
+* **logging** -
+  When enabled, this exposes the `logging` module, and `SettingsExtension::run` installs a `tracing`
+  subscriber which writes logs to stderr. The subscriber is configured by the global `--log-level`
+  and `--log-format` CLI flags, or by the `SETTINGS_EXTENSION_LOG` and
+  `SETTINGS_EXTENSION_LOG_FORMAT` environment variables. Without this feature, the flags are still
+  accepted, but are ignored.
 */
-#[cfg(feature = "extension")]
+#[cfg(any(feature = "extension", feature = "client"))]
 pub mod cli;
+#[cfg(feature = "client")]
+pub mod client;
 #[cfg(feature = "extension")]
 pub mod extension;
 pub mod helper;
+#[cfg(feature = "logging")]
+pub mod logging;
 #[cfg(feature = "extension")]
 pub mod migrate;
 pub mod model;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 #[cfg(feature = "extension")]
 pub use crate::extension::SettingsExtension;
@@ -36,7 +72,9 @@ pub use migrate::{
     Migrator, NoMigration, NullMigrator, NullMigratorExtensionBuilder,
 };
 
-pub use model::{BottlerocketSetting, GenerateResult, SettingsModel};
+pub use model::{
+    BottlerocketSetting, GenerateResult, MigrationResult, SettingsDiff, SettingsModel, UnsetResult,
+};
 
 #[doc(hidden)]
 #[cfg(feature = "extension")]
@@ -0,0 +1,148 @@
+//! Provides logging configuration for settings extensions.
+//!
+//! Settings extensions write their results to stdout, so any logs must be written to stderr to
+//! avoid corrupting the extension's output. [`LogConfig`] installs a `tracing` subscriber which
+//! does so.
+//!
+//! The configuration is taken from the following sources, in order of precedence:
+//! * The global `--log-level` and `--log-format` flags of the extension CLI.
+//! * The [`LOG_ENV_VAR`] and [`LOG_FORMAT_ENV_VAR`] environment variables. [`LOG_ENV_VAR`] accepts
+//!   [filter directives](tracing_subscriber::EnvFilter), such as `warn,my_extension=debug`.
+//! * The defaults, which write `warn` level logs as text.
+//!
+//! [`SettingsExtension::run`](crate::SettingsExtension::run) applies this configuration
+//! automatically. If the extension has already installed its own subscriber, it is left in place.
+use crate::cli::{Cli, LogFormat};
+use snafu::ResultExt;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+pub use error::LoggingError;
+
+/// The environment variable containing filter directives for the extension's logs.
+pub const LOG_ENV_VAR: &str = "SETTINGS_EXTENSION_LOG";
+
+/// The environment variable containing the format of the extension's logs, `text` or `json`.
+pub const LOG_FORMAT_ENV_VAR: &str = "SETTINGS_EXTENSION_LOG_FORMAT";
+
+/// Describes how a settings extension writes logs to stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    filter: String,
+    format: LogFormat,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: LevelFilter::WARN.to_string(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+impl LogConfig {
+    /// Creates a configuration from the [`LOG_ENV_VAR`] and [`LOG_FORMAT_ENV_VAR`] environment
+    /// variables, using the defaults for any that are unset.
+    pub fn from_env() -> Result<Self, LoggingError> {
+        let mut config = Self::default();
+
+        if let Ok(filter) = std::env::var(LOG_ENV_VAR) {
+            config = config.with_filter(filter)?;
+        }
+        if let Ok(format) = std::env::var(LOG_FORMAT_ENV_VAR) {
+            config.format = format
+                .parse()
+                .map_err(|message| error::InvalidFormatSnafu { message }.build())?;
+        }
+
+        Ok(config)
+    }
+
+    /// Sets the filter directives used to select which logs are written.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Result<Self, LoggingError> {
+        let filter = filter.into();
+        EnvFilter::try_new(&filter).context(error::InvalidFilterSnafu {
+            filter: filter.clone(),
+        })?;
+        self.filter = filter;
+        Ok(self)
+    }
+
+    /// Sets the most verbose level of logs which are written.
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.filter = level.to_string();
+        self
+    }
+
+    /// Sets the format of written logs.
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Overrides the configuration with any logging flags given on the command line.
+    pub fn with_cli_args(self, cli: &Cli) -> Self {
+        let config = match cli.log_level {
+            Some(level) => self.with_level(level),
+            None => self,
+        };
+        match cli.log_format {
+            Some(format) => config.with_format(format),
+            None => config,
+        }
+    }
+
+    /// Returns the filter directives used to select which logs are written.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Returns the format of written logs.
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Installs a global subscriber which writes logs to stderr using this configuration.
+    ///
+    /// Returns an error if a global subscriber has already been installed.
+    pub fn try_init(&self) -> Result<(), LoggingError> {
+        let filter = EnvFilter::try_new(&self.filter).context(error::InvalidFilterSnafu {
+            filter: self.filter.clone(),
+        })?;
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr);
+
+        match self.format {
+            LogFormat::Text => subscriber.finish().try_init(),
+            LogFormat::Json => subscriber.json().finish().try_init(),
+        }
+        .context(error::InitSnafu)
+    }
+}
+
+mod error {
+    #![allow(missing_docs)]
+    use snafu::Snafu;
+
+    /// The error type returned when configuring logging.
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility(pub))]
+    pub enum LoggingError {
+        #[snafu(display("Failed to install logger: {}", source))]
+        Init {
+            source: tracing_subscriber::util::TryInitError,
+        },
+
+        #[snafu(display("Invalid log filter '{}': {}", filter, source))]
+        InvalidFilter {
+            filter: String,
+            source: tracing_subscriber::filter::ParseError,
+        },
+
+        #[snafu(display("Invalid log format: {}", message))]
+        InvalidFormat { message: String },
+    }
+}
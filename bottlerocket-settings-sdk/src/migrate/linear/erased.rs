@@ -22,6 +22,8 @@
 //! We use the [`Any`] trait to perform type-erasure and downcasting to the associated model types.
 use super::interface::LinearlyMigrateable;
 use super::{error, LinearMigratorError, MigrationDirection};
+use crate::extension::catch_panic;
+use crate::migrate::PreservedData;
 use crate::model::erased::{AsTypeErasedModel, TypeErasedModel};
 use crate::BottlerocketSetting;
 use snafu::{OptionExt, ResultExt};
@@ -43,6 +45,20 @@ pub trait TypeErasedLinearlyMigrateable {
         &self,
         current: &dyn Any,
         direction: MigrationDirection,
+    ) -> Result<Box<dyn Any>, LinearMigratorError> {
+        self.migrate_preserving(current, direction, &mut PreservedData::new())
+    }
+
+    /// Accepts a type-erased `BottlerocketSettings` implementor and migrates it in the given
+    /// direction.
+    ///
+    /// Data dropped by a backward migration is stored in `preserved`, and data previously
+    /// preserved from the forward migration target is restored by a forward migration.
+    fn migrate_preserving(
+        &self,
+        current: &dyn Any,
+        direction: MigrationDirection,
+        preserved: &mut PreservedData,
     ) -> Result<Box<dyn Any>, LinearMigratorError>;
 
     /// Serializes a type-erased `BottlerocketSettings`.
@@ -61,10 +77,11 @@ impl<T: LinearlyMigrateable + 'static> TypeErasedLinearlyMigrateable for Bottler
         }
     }
 
-    fn migrate(
+    fn migrate_preserving(
         &self,
         current: &dyn Any,
         direction: MigrationDirection,
+        preserved: &mut PreservedData,
     ) -> Result<Box<dyn Any>, LinearMigratorError> {
         let current: &T =
             current
@@ -80,15 +97,26 @@ impl<T: LinearlyMigrateable + 'static> TypeErasedLinearlyMigrateable for Bottler
                         direction,
                         version: T::get_version(),
                     })?;
-                current
-                    .migrate_backward()
+                let (retval, dropped_data) =
+                    with_panic_context(T::get_version(), to_version, direction, || {
+                        current.migrate_backward_preserving()
+                    })
                     .map_err(Into::into)
                     .context(error::SubMigrationSnafu {
                         from_version: T::get_version(),
                         to_version,
                         direction,
-                    })
-                    .map(|retval| Box::new(retval) as Box<dyn Any>)
+                    })?;
+
+                match dropped_data {
+                    Some(dropped_data) => preserved.insert(T::get_version(), dropped_data),
+                    // Nothing was dropped, so any data preserved by an earlier downgrade is stale.
+                    None => {
+                        preserved.take(T::get_version());
+                    }
+                }
+
+                Ok(Box::new(retval) as Box<dyn Any>)
             }
             MigrationDirection::Forward => {
                 let to_version =
@@ -96,15 +124,17 @@ impl<T: LinearlyMigrateable + 'static> TypeErasedLinearlyMigrateable for Bottler
                         direction,
                         version: T::get_version(),
                     })?;
-                current
-                    .migrate_forward()
-                    .map_err(Into::into)
-                    .context(error::SubMigrationSnafu {
-                        from_version: T::get_version(),
-                        to_version,
-                        direction,
-                    })
-                    .map(|retval| Box::new(retval) as Box<dyn Any>)
+                let restored = preserved.take(to_version);
+                with_panic_context(T::get_version(), to_version, direction, || {
+                    current.migrate_forward_restoring(restored)
+                })
+                .map_err(Into::into)
+                .context(error::SubMigrationSnafu {
+                    from_version: T::get_version(),
+                    to_version,
+                    direction,
+                })
+                .map(|retval| Box::new(retval) as Box<dyn Any>)
             }
         }
     }
@@ -120,6 +150,24 @@ impl<T: LinearlyMigrateable + 'static> TypeErasedLinearlyMigrateable for Bottler
     }
 }
 
+/// Runs a sub-migration, adding the versions it migrates between to the message of any panic which
+/// unwinds out of it.
+///
+/// The panic continues to unwind, so that the settings extension reports it like any other panic.
+fn with_panic_context<R>(
+    from_version: &'static str,
+    to_version: &'static str,
+    direction: MigrationDirection,
+    migrate: impl FnOnce() -> R,
+) -> R {
+    catch_panic(migrate).unwrap_or_else(|message| {
+        std::panic::resume_unwind(Box::new(format!(
+            "Sub-migration of setting {} from '{}' to '{}' panicked: {}",
+            direction, from_version, to_version, message
+        )))
+    })
+}
+
 // We need to implement `AsModel` to satisfy the `SettingsExtension` and `Migrator` interfaces.
 // Even if `TypeErasedLinearlyMigrateable` had `AsModel` as a supertrait, supertraits do not extend
 // to trait objects.
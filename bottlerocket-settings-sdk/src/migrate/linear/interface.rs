@@ -156,4 +156,35 @@ pub trait LinearlyMigrateable: SettingsModel {
 
     /// Migrates this settings value backward.
     fn migrate_backward(&self) -> Result<Self::BackwardMigrationTarget, Self::ErrorKind>;
+
+    /// Migrates this settings value backward, also returning any data which cannot be represented
+    /// in the backward migration target.
+    ///
+    /// When a migration is performed with data preservation enabled, the returned data is stored by
+    /// the SDK and handed to the backward target's
+    /// [`migrate_forward_restoring`](Self::migrate_forward_restoring) on a later forward migration,
+    /// allowing lossless downgrade/upgrade round trips.
+    ///
+    /// The default implementation preserves nothing and defers to
+    /// [`migrate_backward`](Self::migrate_backward).
+    fn migrate_backward_preserving(
+        &self,
+    ) -> Result<(Self::BackwardMigrationTarget, Option<serde_json::Value>), Self::ErrorKind> {
+        self.migrate_backward().map(|target| (target, None))
+    }
+
+    /// Migrates this settings value forward, restoring any data that the forward migration target
+    /// previously returned from
+    /// [`migrate_backward_preserving`](Self::migrate_backward_preserving).
+    ///
+    /// `preserved` is `None` if no data was preserved. In that case, implementations should behave
+    /// identically to [`migrate_forward`](Self::migrate_forward), which is what the default
+    /// implementation does.
+    fn migrate_forward_restoring(
+        &self,
+        preserved: Option<serde_json::Value>,
+    ) -> Result<Self::ForwardMigrationTarget, Self::ErrorKind> {
+        let _ = preserved;
+        self.migrate_forward()
+    }
 }
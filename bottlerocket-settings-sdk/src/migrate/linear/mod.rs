@@ -1,6 +1,8 @@
 //! Provides the [`LinearlyMigrateable`] trait that is needed to use the [`LinearMigrator`] with a
 //! [`SettingsModel`](crate::model::SettingsModel).
-use super::{MigrationResult, Migrator, ModelStore, NoMigration};
+use super::{
+    MigrationResult, Migrator, ModelStore, NoMigration, PreservedData, PreservingMigrationResult,
+};
 use erased::TypeErasedLinearlyMigrateable;
 use snafu::OptionExt;
 use std::any::Any;
@@ -45,52 +47,13 @@ impl Migrator for LinearMigrator {
         starting_version: &str,
         target_version: &str,
     ) -> Result<serde_json::Value, LinearMigratorError> {
-        debug!(starting_version, target_version, "Starting migration.",);
-
-        let starting_model =
-            models
-                .get_model(starting_version)
-                .context(error::NoSuchModelSnafu {
-                    version: starting_version.to_string(),
-                })?;
-
-        let mut migration_route = self
-            .find_migration_route(models, starting_version, target_version)
-            .context(error::NoMigrationRouteSnafu {
-                starting_version: starting_version.to_string(),
-                target_version: target_version.to_string(),
-            })?;
-
-        debug!(
+        self.migrate_along_route(
+            models,
+            starting_value,
             starting_version,
-            target_version, "Performing all submigrations to satisfy migration."
-        );
-        // Consume the route of migration directions, keeping track of the data and version as we go
-        let result = migration_route
-            .try_fold(
-                (starting_value, starting_model),
-                |(curr_value, curr_model), next_direction| {
-                    let current_version = curr_model.as_model().get_version();
-                    let next_version = curr_model.migrates_to(next_direction).expect(
-                        "Failed to find migration which was previously found during route \
-                        selection.",
-                    );
-                    debug!(current_version, target_version, "Performing submigration.");
-
-                    let next_model = models.get_model(next_version).expect(
-                        "Failed to find migration which was previously found during route \
-                        selection.",
-                    );
-                    let next_value = curr_model.migrate(curr_value.as_ref(), next_direction)?;
-
-                    Ok((next_value, next_model))
-                },
-            )
-            .and_then(|(final_value, final_model)| final_model.serialize(final_value.as_ref()));
-
-        debug!(starting_version, target_version, "Migration complete.");
-
-        result
+            target_version,
+            &mut PreservedData::new(),
+        )
     }
 
     /// Migrates a given settings value to all other available versions.
@@ -102,7 +65,7 @@ impl Migrator for LinearMigrator {
         models: &dyn ModelStore<ModelKind = Self::ModelKind>,
         starting_value: Box<dyn Any>,
         starting_version: &str,
-    ) -> Result<Vec<super::MigrationResult>, Self::ErrorKind> {
+    ) -> Result<Vec<MigrationResult>, Self::ErrorKind> {
         debug!(starting_version, "Starting migrations.");
 
         let starting_model = models
@@ -114,7 +77,7 @@ impl Migrator for LinearMigrator {
 
         let mut results = Vec::with_capacity(models.len());
         results.push(MigrationResult {
-            version: starting_model.as_model().get_version(),
+            version: starting_model.as_model().get_version().to_string(),
             value: starting_model.serialize(starting_value.as_ref())?,
         });
 
@@ -139,7 +102,7 @@ impl Migrator for LinearMigrator {
                         let next_value = curr_model.migrate(curr_value, direction)?;
 
                         results.push(MigrationResult {
-                            version: next_version,
+                            version: next_version.to_string(),
                             value: next_model.serialize(next_value.as_ref())?,
                         });
 
@@ -156,10 +119,41 @@ impl Migrator for LinearMigrator {
 
         debug!(starting_version, "Flood migration complete.");
 
-        results.sort_by_key(|result| result.version);
+        results.sort_by(|lhs, rhs| lhs.version.cmp(&rhs.version));
 
         Ok(results)
     }
+
+    /// Migrates data from a starting version to a target version, preserving data dropped by
+    /// backward migrations.
+    ///
+    /// Each backward migration stores the data returned by
+    /// [`LinearlyMigrateable::migrate_backward_preserving`] under the version it migrated from.
+    /// Each forward migration restores the data stored under the version it migrates to via
+    /// [`LinearlyMigrateable::migrate_forward_restoring`].
+    // Preserved data is dropped from the setting's value, and may be sensitive, so it isn't logged.
+    #[instrument(skip(self, models, preserved_data), err)]
+    fn perform_preserving_migration(
+        &self,
+        models: &dyn ModelStore<ModelKind = Self::ModelKind>,
+        starting_value: Box<dyn Any>,
+        starting_version: &str,
+        target_version: &str,
+        mut preserved_data: PreservedData,
+    ) -> Result<PreservingMigrationResult, LinearMigratorError> {
+        let value = self.migrate_along_route(
+            models,
+            starting_value,
+            starting_version,
+            target_version,
+            &mut preserved_data,
+        )?;
+
+        Ok(PreservingMigrationResult {
+            value,
+            preserved_data,
+        })
+    }
 }
 
 /// Iterates through models, following a linear migration chain starting from a given model and moving in a given
@@ -213,6 +207,68 @@ impl std::fmt::Display for MigrationDirection {
 }
 
 impl LinearMigrator {
+    /// Migrates data from a starting version to a target version by following the migration chain,
+    /// storing and restoring dropped data in `preserved_data` as it goes.
+    fn migrate_along_route(
+        &self,
+        models: &dyn ModelStore<ModelKind = LinearMigratorModel>,
+        starting_value: Box<dyn Any>,
+        starting_version: &str,
+        target_version: &str,
+        preserved_data: &mut PreservedData,
+    ) -> Result<serde_json::Value, LinearMigratorError> {
+        debug!(starting_version, target_version, "Starting migration.",);
+
+        let starting_model =
+            models
+                .get_model(starting_version)
+                .context(error::NoSuchModelSnafu {
+                    version: starting_version.to_string(),
+                })?;
+
+        let mut migration_route = self
+            .find_migration_route(models, starting_version, target_version)
+            .context(error::NoMigrationRouteSnafu {
+                starting_version: starting_version.to_string(),
+                target_version: target_version.to_string(),
+            })?;
+
+        debug!(
+            starting_version,
+            target_version, "Performing all submigrations to satisfy migration."
+        );
+        // Consume the route of migration directions, keeping track of the data and version as we go
+        let result = migration_route
+            .try_fold(
+                (starting_value, starting_model),
+                |(curr_value, curr_model), next_direction| {
+                    let current_version = curr_model.as_model().get_version();
+                    let next_version = curr_model.migrates_to(next_direction).expect(
+                        "Failed to find migration which was previously found during route \
+                        selection.",
+                    );
+                    debug!(current_version, target_version, "Performing submigration.");
+
+                    let next_model = models.get_model(next_version).expect(
+                        "Failed to find migration which was previously found during route \
+                        selection.",
+                    );
+                    let next_value = curr_model.migrate_preserving(
+                        curr_value.as_ref(),
+                        next_direction,
+                        preserved_data,
+                    )?;
+
+                    Ok((next_value, next_model))
+                },
+            )
+            .and_then(|(final_value, final_model)| final_model.serialize(final_value.as_ref()));
+
+        debug!(starting_version, target_version, "Migration complete.");
+
+        result
+    }
+
     /// Returns an iterator of migrations to be performed to transform data from a starting version to a target version.
     fn find_migration_route(
         &self,
@@ -252,7 +308,7 @@ impl LinearMigrator {
             .then_some((0, Forward)) // 0 hops required for "identity" migration
             .or_else(|| search_in_direction(Forward))
             .or_else(|| search_in_direction(Backward))
-            .map(|(num_hops, direction)| std::iter::repeat(direction).take(num_hops))
+            .map(|(num_hops, direction)| std::iter::repeat_n(direction, num_hops))
     }
 }
 
@@ -510,23 +566,23 @@ mod test {
 
         let expected_flood_results = vec![
             MigrationResult {
-                version: "v1",
+                version: "v1".to_string(),
                 value: serde_json::to_value(BasicV1::new()).unwrap(),
             },
             MigrationResult {
-                version: "v2",
+                version: "v2".to_string(),
                 value: serde_json::to_value(BasicV2::new()).unwrap(),
             },
             MigrationResult {
-                version: "v3",
+                version: "v3".to_string(),
                 value: serde_json::to_value(BasicV3::new()).unwrap(),
             },
             MigrationResult {
-                version: "v4",
+                version: "v4".to_string(),
                 value: serde_json::to_value(BasicV4::new()).unwrap(),
             },
             MigrationResult {
-                version: "v5",
+                version: "v5".to_string(),
                 value: serde_json::to_value(BasicV5::new()).unwrap(),
             },
         ];
@@ -547,4 +603,220 @@ mod test {
             assert_eq!(results, expected_flood_results)
         });
     }
+
+    // A pair of models where migrating backward drops data, which can be preserved.
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct PreservingV1 {
+        name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct PreservingV2 {
+        name: String,
+        nickname: Option<String>,
+    }
+
+    macro_rules! trivial_settings_model {
+        ($name:ident, $repr:expr) => {
+            impl crate::SettingsModel for $name {
+                type PartialKind = Self;
+                type ErrorKind = Infallible;
+
+                fn get_version() -> &'static str {
+                    $repr
+                }
+
+                fn set(_: Option<Self>, _: Self) -> Result<(), Infallible> {
+                    Ok(())
+                }
+
+                fn generate(
+                    _: Option<Self::PartialKind>,
+                    _: Option<serde_json::Value>,
+                ) -> Result<crate::GenerateResult<Self::PartialKind, Self>, Infallible> {
+                    unimplemented!()
+                }
+
+                fn validate(_: Self, _: Option<serde_json::Value>) -> Result<(), Infallible> {
+                    Ok(())
+                }
+            }
+        };
+    }
+
+    trivial_settings_model!(PreservingV1, "v1");
+    trivial_settings_model!(PreservingV2, "v2");
+
+    impl LinearlyMigrateable for PreservingV1 {
+        type ForwardMigrationTarget = PreservingV2;
+        type BackwardMigrationTarget = NoMigration;
+
+        fn migrate_forward(&self) -> Result<PreservingV2, Infallible> {
+            self.migrate_forward_restoring(None)
+        }
+
+        fn migrate_forward_restoring(
+            &self,
+            preserved: Option<serde_json::Value>,
+        ) -> Result<PreservingV2, Infallible> {
+            Ok(PreservingV2 {
+                name: self.name.clone(),
+                nickname: preserved.and_then(|p| serde_json::from_value(p).ok()),
+            })
+        }
+
+        fn migrate_backward(&self) -> Result<NoMigration, Infallible> {
+            NoMigration::no_defined_migration()
+        }
+    }
+
+    impl LinearlyMigrateable for PreservingV2 {
+        type ForwardMigrationTarget = NoMigration;
+        type BackwardMigrationTarget = PreservingV1;
+
+        fn migrate_forward(&self) -> Result<NoMigration, Infallible> {
+            NoMigration::no_defined_migration()
+        }
+
+        fn migrate_backward(&self) -> Result<PreservingV1, Infallible> {
+            self.migrate_backward_preserving().map(|(v1, _)| v1)
+        }
+
+        fn migrate_backward_preserving(
+            &self,
+        ) -> Result<(PreservingV1, Option<serde_json::Value>), Infallible> {
+            Ok((
+                PreservingV1 {
+                    name: self.name.clone(),
+                },
+                self.nickname.as_ref().map(|n| serde_json::json!(n)),
+            ))
+        }
+    }
+
+    fn preserving_extension() -> crate::SettingsExtension<LinearMigrator, LinearMigratorModel> {
+        LinearMigratorExtensionBuilder::with_name("preserving")
+            .with_models(vec![
+                BottlerocketSetting::<PreservingV1>::model(),
+                BottlerocketSetting::<PreservingV2>::model(),
+            ])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_preserving_migration_round_trip() {
+        let models = preserving_extension();
+        let original = PreservingV2 {
+            name: "Robert".to_string(),
+            nickname: Some("Bob".to_string()),
+        };
+
+        let downgraded = LinearMigrator
+            .perform_preserving_migration(
+                &models,
+                Box::new(original) as Box<dyn Any>,
+                "v2",
+                "v1",
+                PreservedData::new(),
+            )
+            .unwrap();
+        assert_eq!(downgraded.value, serde_json::json!({"name": "Robert"}));
+        assert_eq!(
+            downgraded.preserved_data.get("v2"),
+            Some(&serde_json::json!("Bob"))
+        );
+
+        let upgraded = LinearMigrator
+            .perform_preserving_migration(
+                &models,
+                Box::new(PreservingV1 {
+                    name: "Robert".to_string(),
+                }) as Box<dyn Any>,
+                "v1",
+                "v2",
+                downgraded.preserved_data,
+            )
+            .unwrap();
+        assert_eq!(
+            upgraded.value,
+            serde_json::json!({"name": "Robert", "nickname": "Bob"})
+        );
+        assert!(upgraded.preserved_data.is_empty());
+    }
+
+    #[test]
+    fn test_preserving_migration_discards_stale_data() {
+        let models = preserving_extension();
+        let downgrade = |value: PreservingV2, preserved_data| {
+            LinearMigrator
+                .perform_preserving_migration(
+                    &models,
+                    Box::new(value) as Box<dyn Any>,
+                    "v2",
+                    "v1",
+                    preserved_data,
+                )
+                .unwrap()
+        };
+
+        // Downgrade, preserving the nickname.
+        let downgraded = downgrade(
+            PreservingV2 {
+                name: "Robert".to_string(),
+                nickname: Some("Bob".to_string()),
+            },
+            PreservedData::new(),
+        );
+        assert!(downgraded.preserved_data.get("v2").is_some());
+
+        // The nickname is then removed in v2, and the setting is downgraded again with the data
+        // preserved by the first downgrade.
+        let downgraded = downgrade(
+            PreservingV2 {
+                name: "Robert".to_string(),
+                nickname: None,
+            },
+            downgraded.preserved_data,
+        );
+        assert!(downgraded.preserved_data.is_empty());
+
+        // Upgrading does not restore the stale nickname.
+        let upgraded = LinearMigrator
+            .perform_preserving_migration(
+                &models,
+                Box::new(PreservingV1 {
+                    name: "Robert".to_string(),
+                }) as Box<dyn Any>,
+                "v1",
+                "v2",
+                downgraded.preserved_data,
+            )
+            .unwrap();
+        assert_eq!(
+            upgraded.value,
+            serde_json::json!({"name": "Robert", "nickname": null})
+        );
+    }
+
+    #[test]
+    fn test_non_preserving_migration_drops_data() {
+        let models = preserving_extension();
+        let original = PreservingV2 {
+            name: "Robert".to_string(),
+            nickname: Some("Bob".to_string()),
+        };
+
+        let downgraded = LinearMigrator
+            .perform_migration(&models, Box::new(original) as Box<dyn Any>, "v2", "v1")
+            .unwrap();
+        let downgraded: PreservingV1 = serde_json::from_value(downgraded).unwrap();
+
+        assert_eq!(
+            LinearMigrator
+                .perform_migration(&models, Box::new(downgraded) as Box<dyn Any>, "v1", "v2")
+                .unwrap(),
+            serde_json::json!({"name": "Robert", "nickname": null})
+        );
+    }
 }
@@ -5,9 +5,11 @@
 //! The [`Migrator`](self::Migrator) trait, is also provided, which allows settings extensions
 //! to customize how they are migrated between different versions.
 use crate::model::erased::AsTypeErasedModel;
+pub use crate::model::MigrationResult;
 use crate::{GenerateResult, SettingsModel};
 use serde::{Deserialize, Serialize};
 use std::any::Any;
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::fmt::Debug;
 
@@ -64,15 +66,78 @@ pub trait Migrator: Debug {
         starting_value: Box<dyn Any>,
         starting_version: &str,
     ) -> Result<Vec<MigrationResult>, Self::ErrorKind>;
+
+    /// Migrates a given settings value from its starting version to a target version, preserving
+    /// any data which is dropped along the way.
+    ///
+    /// `preserved_data` contains data preserved by previous migrations, which is restored where
+    /// possible. The returned [`PreservingMigrationResult`] contains the migrated value, as well as
+    /// all data which remains preserved after the migration.
+    ///
+    /// The default implementation preserves nothing: it performs the migration with
+    /// [`perform_migration`](Self::perform_migration) and returns the given preserved data
+    /// unchanged.
+    fn perform_preserving_migration(
+        &self,
+        models: &dyn ModelStore<ModelKind = Self::ModelKind>,
+        starting_value: Box<dyn Any>,
+        starting_version: &str,
+        target_version: &str,
+        preserved_data: PreservedData,
+    ) -> Result<PreservingMigrationResult, Self::ErrorKind> {
+        let value =
+            self.perform_migration(models, starting_value, starting_version, target_version)?;
+
+        Ok(PreservingMigrationResult {
+            value,
+            preserved_data,
+        })
+    }
+}
+
+/// Data which was dropped by migrations, keyed by the version of the setting that it was dropped
+/// from.
+///
+/// Preserved data is opaque to the settings system. It is returned alongside migrated values so
+/// that it can be handed back to a later migration, which restores it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(transparent)]
+pub struct PreservedData(BTreeMap<String, serde_json::Value>);
+
+impl PreservedData {
+    /// Creates an empty set of preserved data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the data preserved from the given setting version, if any.
+    pub fn get(&self, version: &str) -> Option<&serde_json::Value> {
+        self.0.get(version)
+    }
+
+    /// Stores data preserved from the given setting version, replacing any previous data.
+    pub fn insert(&mut self, version: &str, data: serde_json::Value) {
+        self.0.insert(version.to_string(), data);
+    }
+
+    /// Removes and returns the data preserved from the given setting version, if any.
+    pub fn take(&mut self, version: &str) -> Option<serde_json::Value> {
+        self.0.remove(version)
+    }
+
+    /// Returns whether or not any data is preserved.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
-/// An individual migration result from a batch migration.
+/// The result of a migration which preserves dropped data.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
-pub struct MigrationResult {
-    /// The version resulting from the migration.
-    pub version: &'static str,
+pub struct PreservingMigrationResult {
     /// The value resulting from the migration.
     pub value: serde_json::Value,
+    /// Data which could not be represented in the resulting version.
+    pub preserved_data: PreservedData,
 }
 
 /// A type that holds settings models, used to resolve version -> model lookups during migrations.
@@ -0,0 +1,161 @@
+//! Provides [`SettingsDiff`], a structured description of the changes between a setting's current
+//! value and a new value.
+//!
+//! Paths in a diff are [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) into the
+//! model's JSON representation. Objects are compared member by member and arrays element by element,
+//! so each path names the most specific value which differs.
+//!
+//! ```
+//! use bottlerocket_settings_sdk::model::diff::SettingsDiff;
+//! use serde_json::json;
+//!
+//! let current = json!({"name": "motd", "lines": ["hi"], "color": "red"});
+//! let target = json!({"name": "motd", "lines": ["hi", "there"], "size": 3});
+//!
+//! let diff = SettingsDiff::between(Some(&current), &target);
+//! assert_eq!(diff.added, vec!["/lines/1", "/size"]);
+//! assert_eq!(diff.removed, vec!["/color"]);
+//! assert!(diff.changed.is_empty());
+//! assert!(diff.touches("/lines"));
+//! assert!(!diff.touches("/name"));
+//! ```
+use super::redact::segments;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The paths at which a new value of a setting differs from its current value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SettingsDiff {
+    /// Paths which are present in the new value, but not the current value.
+    pub added: Vec<String>,
+    /// Paths which are present in the current value, but not the new value.
+    pub removed: Vec<String>,
+    /// Paths whose value differs between the current and new values.
+    pub changed: Vec<String>,
+}
+
+impl SettingsDiff {
+    /// Computes the differences between a setting's current value, if any, and a new value.
+    ///
+    /// If there is no current value, the entire new value is added at the empty pointer `""`.
+    pub fn between(current: Option<&Value>, target: &Value) -> Self {
+        let mut diff = Self::default();
+        match current {
+            Some(current) => diff.compare(String::new(), current, target),
+            None => diff.added.push(String::new()),
+        }
+        diff
+    }
+
+    /// Returns whether the values are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns whether the value at the given JSON pointer differs, either because it or one of
+    /// its descendants was added, removed or changed, or because one of its ancestors was.
+    ///
+    /// A `*` segment in the pointer matches every member of an object or element of an array.
+    pub fn touches(&self, pointer: &str) -> bool {
+        self.touched(pointer).next().is_some()
+    }
+
+    /// Returns the paths of the differing values which match the given JSON pointer, in which a
+    /// `*` segment matches every member of an object or element of an array.
+    ///
+    /// Where the difference is in an ancestor of the matching values, the returned path extends
+    /// the ancestor's path with the remaining segments of the pointer, which may include `*`.
+    pub fn touched<'a>(&'a self, pointer: &str) -> impl Iterator<Item = String> + 'a {
+        let pattern = segments(pointer);
+        self.paths().filter_map(move |path| {
+            let path = segments(path);
+            let shared = path.len().min(pattern.len());
+            let matches = path
+                .iter()
+                .zip(&pattern)
+                .all(|(segment, expected)| expected == "*" || segment == expected);
+
+            matches.then(|| {
+                path[..shared]
+                    .iter()
+                    .chain(&pattern[shared..])
+                    .map(|segment| format!("/{}", escape(segment)))
+                    .collect()
+            })
+        })
+    }
+
+    /// Returns every path in the diff.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.changed)
+            .map(String::as_str)
+    }
+
+    /// Records the differences between the values at `path`.
+    fn compare(&mut self, path: String, current: &Value, target: &Value) {
+        match (current, target) {
+            (Value::Object(current), Value::Object(target)) => {
+                let mut keys: Vec<&String> = current.keys().chain(target.keys()).collect();
+                keys.sort();
+                keys.dedup();
+
+                for key in keys {
+                    let path = format!("{}/{}", path, escape(key));
+                    self.compare_member(path, current.get(key), target.get(key));
+                }
+            }
+            (Value::Array(current), Value::Array(target)) => {
+                for index in 0..current.len().max(target.len()) {
+                    let path = format!("{}/{}", path, index);
+                    self.compare_member(path, current.get(index), target.get(index));
+                }
+            }
+            (current, target) if current != target => self.changed.push(path),
+            _ => {}
+        }
+    }
+
+    /// Records the differences between members of an object or array, either of which may be
+    /// missing.
+    fn compare_member(&mut self, path: String, current: Option<&Value>, target: Option<&Value>) {
+        match (current, target) {
+            (Some(current), Some(target)) => self.compare(path, current, target),
+            (None, Some(_)) => self.added.push(path),
+            (Some(_), None) => self.removed.push(path),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Escapes a key for use as a JSON pointer segment.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Returns the non-null values found by following the given JSON pointer from `value`, in which a
+/// `*` segment matches every member of an object or element of an array.
+pub(crate) fn values_at<'a>(value: &'a Value, pointer: &str) -> Vec<&'a Value> {
+    let mut values = vec![value];
+    for segment in segments(pointer) {
+        values = values
+            .into_iter()
+            .flat_map(|value| match (value, segment.as_str()) {
+                (Value::Object(members), "*") => members.values().collect(),
+                (Value::Array(elements), "*") => elements.iter().collect(),
+                (Value::Object(members), key) => members.get(key).into_iter().collect(),
+                (Value::Array(elements), index) => index
+                    .parse()
+                    .ok()
+                    .and_then(|i: usize| elements.get(i))
+                    .into_iter()
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+    }
+    values.retain(|value| !value.is_null());
+    values
+}
@@ -5,9 +5,17 @@
 //!
 //! This module contains traits which erase the underlying [`SettingsModel`] types, allowing the
 //! SDK to refer to the [`SettingsModel`]s as a collection of trait objects.
-use super::{error, BottlerocketSetting, BottlerocketSettingError, GenerateResult, SettingsModel};
+use super::diff::values_at;
+use super::redact::{deserialize_redacted, redact_value};
+use super::{
+    error, BottlerocketSetting, BottlerocketSettingError, GenerateResult, SettingsDiff,
+    SettingsModel, UnsetResult,
+};
+use crate::helper::{HelperDef, HelperInvocation};
+use serde::de::DeserializeOwned;
 use snafu::{OptionExt, ResultExt};
 use std::any::Any;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use tracing::{debug, instrument};
 
@@ -33,6 +41,14 @@ pub trait TypeErasedModel: Debug {
     /// Returns the version of the model, e.g. "v1".
     fn get_version(&self) -> &'static str;
 
+    /// Returns the JSON pointers to sensitive values within the model.
+    fn sensitive_fields(&self) -> Vec<&'static str>;
+
+    /// Returns a copy of a value of this model with its sensitive values redacted.
+    fn redact(&self, value: &serde_json::Value) -> serde_json::Value {
+        redact_value(value, &self.sensitive_fields())
+    }
+
     /// Determines whether this setting can be set to the `target` value, given its current value.
     ///
     /// The returned value is what is ultimately set in the settings datastore. While this leaves
@@ -44,6 +60,24 @@ pub trait TypeErasedModel: Debug {
         target: serde_json::Value,
     ) -> Result<(), BottlerocketSettingError>;
 
+    /// Computes the differences between the current value of this setting and a `target` value.
+    ///
+    /// Both values are parsed as the model first, so the diff reflects any defaults filled in while
+    /// parsing.
+    fn diff(
+        &self,
+        current: Option<serde_json::Value>,
+        target: serde_json::Value,
+    ) -> Result<SettingsDiff, BottlerocketSettingError>;
+
+    /// Determines whether this setting can be unset, given its current value.
+    ///
+    /// Any value which should replace the removed value is checked as `set` would check it.
+    fn unset(
+        &self,
+        current: Option<serde_json::Value>,
+    ) -> Result<UnsetResult<serde_json::Value>, BottlerocketSettingError>;
+
     /// Generates default values at system start.
     ///
     /// The settings system repeatedly invokes `generate` on all settings until they have
@@ -74,14 +108,145 @@ pub trait TypeErasedModel: Debug {
         value: serde_json::Value,
     ) -> Result<Box<dyn Any>, BottlerocketSettingError>;
 
+    /// Returns the template helpers associated with this model version.
+    fn template_helpers(
+        &self,
+    ) -> Result<HashMap<String, Box<dyn HelperDef>>, BottlerocketSettingError>;
+
     /// Executes a template helper associated with this model version.
+    ///
+    /// The invocation's [`HelperContext`](crate::helper::HelperContext) is filled in with the
+    /// version of this model.
     fn execute_template_helper(
         &self,
         helper_name: &str,
-        args: Vec<serde_json::Value>,
+        invocation: HelperInvocation,
     ) -> Result<serde_json::Value, BottlerocketSettingError>;
 }
 
+/// Deserializes an input to a model's operation, redacting the model's sensitive values from the
+/// input and the deserialization error should it fail.
+fn deserialize_input<T: SettingsModel, V: DeserializeOwned>(
+    input: serde_json::Value,
+    input_type: &'static str,
+) -> Result<V, BottlerocketSettingError> {
+    deserialize_redacted(input.clone(), &T::sensitive_fields()).context(
+        error::DeserializeInputSnafu {
+            version: T::get_version(),
+            input: redact_value(&input, &T::sensitive_fields()),
+            input_type,
+        },
+    )
+}
+
+/// Checks whether a model can be set to the `target` value, enforcing the model's immutable and
+/// write-once fields and passing the model the diff from its current value.
+fn check_set<T: SettingsModel>(
+    current: Option<T>,
+    target: T,
+) -> Result<(), BottlerocketSettingError> {
+    let current_value = current.as_ref().map(to_json).transpose()?;
+    let diff = SettingsDiff::between(current_value.as_ref(), &to_json(&target)?);
+
+    if let Some(current_value) = &current_value {
+        check_fixed_fields::<T>(current_value, &diff)?;
+    }
+
+    T::set_with_diff(current, target, &diff)
+        .map_err(Into::into)
+        .context(error::SetSettingSnafu {
+            version: T::get_version(),
+        })
+}
+
+/// Rejects any difference from the current value in the model's immutable fields, or in its
+/// write-once fields which already have a value.
+fn check_fixed_fields<T: SettingsModel>(
+    current_value: &serde_json::Value,
+    diff: &SettingsDiff,
+) -> Result<(), BottlerocketSettingError> {
+    for field in T::immutable_fields() {
+        if let Some(field) = diff.touched(field).next() {
+            return error::ImmutableFieldSnafu {
+                version: T::get_version(),
+                field,
+            }
+            .fail();
+        }
+    }
+
+    for field in T::write_once_fields() {
+        if let Some(field) = diff
+            .touched(field)
+            .find(|path| !values_at(current_value, path).is_empty())
+        {
+            return error::WriteOnceFieldSnafu {
+                version: T::get_version(),
+                field,
+            }
+            .fail();
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the diff between the JSON representations of two values of a model.
+fn diff_values<T: SettingsModel>(
+    current: Option<&T>,
+    target: &T,
+) -> Result<SettingsDiff, BottlerocketSettingError> {
+    let current = current.map(to_json).transpose()?;
+    Ok(SettingsDiff::between(current.as_ref(), &to_json(target)?))
+}
+
+/// Serializes a value of a model, so that it can be compared with another.
+fn to_json<T: SettingsModel>(value: &T) -> Result<serde_json::Value, BottlerocketSettingError> {
+    serde_json::to_value(value).context(error::SerializeResultSnafu {
+        version: T::get_version(),
+        operation: "diff",
+    })
+}
+
+/// Returns the names of the settings in a JSON object of settings.
+///
+/// Other settings' values are not logged, as they may be sensitive.
+fn setting_names(settings: &serde_json::Value) -> Vec<&str> {
+    settings
+        .as_object()
+        .map(|settings| settings.keys().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Executes the named helper from a set of helpers provided for the given model version.
+///
+/// The invocation's [`HelperContext`](crate::helper::HelperContext) is filled in with the version.
+pub(crate) fn execute_template_helper(
+    helpers: &HashMap<String, Box<dyn HelperDef>>,
+    helper_name: &str,
+    version: &'static str,
+    mut invocation: HelperInvocation,
+) -> Result<serde_json::Value, BottlerocketSettingError> {
+    invocation
+        .context
+        .get_or_insert_with(Default::default)
+        .version = version.to_string();
+
+    let helper = helpers
+        .get(helper_name)
+        .context(error::FetchTemplateHelperSnafu {
+            helper_name: helper_name.to_string(),
+            helper_version: version,
+        })?;
+
+    helper
+        .execute(invocation)
+        .context(error::ExecuteTemplateHelperSnafu {
+            helper_name: helper_name.to_string(),
+            helper_version: version,
+        })
+}
+
 /// A helper trait used to "upcast" supertraits over the [`TypeErasedModel`] trait.
 ///
 /// This is required until Rust supports trait upcast coercion.
@@ -101,42 +266,90 @@ impl<T: SettingsModel + 'static> TypeErasedModel for BottlerocketSetting<T> {
         T::get_version()
     }
 
-    #[instrument(skip(self), err)]
+    fn sensitive_fields(&self) -> Vec<&'static str> {
+        T::sensitive_fields()
+    }
+
+    #[instrument(skip_all, err)]
     fn set(
         &self,
         current: Option<serde_json::Value>,
         target: serde_json::Value,
     ) -> Result<(), BottlerocketSettingError> {
         debug!(
-            current_value = current.as_ref().map(|v| v.to_string()),
-            target_value = target.to_string(),
+            current_value = current.as_ref().map(|v| self.redact(v).to_string()),
+            target_value = self.redact(&target).to_string(),
             version = %T::get_version(),
             "Setting new value for setting."
         );
         let current = current
-            .map(|v| {
-                serde_json::from_value(v.clone()).context(error::DeserializeInputSnafu {
-                    version: T::get_version(),
-                    input: v,
-                    input_type: "current-value",
-                })
-            })
+            .map(|v| deserialize_input::<T, _>(v, "current-value"))
+            .transpose()?;
+
+        let target = deserialize_input::<T, _>(target, "target-value")?;
+        check_set::<T>(current, target)
+    }
+
+    #[instrument(skip_all, err)]
+    fn diff(
+        &self,
+        current: Option<serde_json::Value>,
+        target: serde_json::Value,
+    ) -> Result<SettingsDiff, BottlerocketSettingError> {
+        debug!(
+            current_value = current.as_ref().map(|v| self.redact(v).to_string()),
+            target_value = self.redact(&target).to_string(),
+            version = %T::get_version(),
+            "Computing diff for setting."
+        );
+        let current = current
+            .map(|v| deserialize_input::<T, _>(v, "current-value"))
             .transpose()?;
 
-        let target =
-            serde_json::from_value(target.clone()).context(error::DeserializeInputSnafu {
+        let target = deserialize_input::<T, _>(target, "target-value")?;
+        diff_values::<T>(current.as_ref(), &target)
+    }
+
+    #[instrument(skip_all, err)]
+    fn unset(
+        &self,
+        current: Option<serde_json::Value>,
+    ) -> Result<UnsetResult<serde_json::Value>, BottlerocketSettingError> {
+        debug!(
+            current_value = current.as_ref().map(|v| self.redact(v).to_string()),
+            version = %T::get_version(),
+            "Unsetting value for setting."
+        );
+        let parse_current = || {
+            current
+                .clone()
+                .map(|v| deserialize_input::<T, _>(v, "current-value"))
+                .transpose()
+        };
+
+        let result =
+            T::unset(parse_current()?)
+                .map_err(Into::into)
+                .context(error::UnsetSettingSnafu {
+                    version: T::get_version(),
+                })?;
+
+        let UnsetResult::Replace(replacement) = result else {
+            return Ok(UnsetResult::Remove);
+        };
+
+        // Replacement values are subject to the same checks as any other new value.
+        let serialized =
+            serde_json::to_value(&replacement).context(error::SerializeResultSnafu {
                 version: T::get_version(),
-                input: target,
-                input_type: "target-value",
+                operation: "unset",
             })?;
-        T::set(current, target)
-            .map_err(Into::into)
-            .context(error::SetSettingSnafu {
-                version: T::get_version(),
-            })
+        check_set::<T>(parse_current()?, replacement)?;
+
+        Ok(UnsetResult::Replace(serialized))
     }
 
-    #[instrument(skip(self), err)]
+    #[instrument(skip_all, err)]
     fn generate(
         &self,
         existing_partial: Option<serde_json::Value>,
@@ -144,20 +357,14 @@ impl<T: SettingsModel + 'static> TypeErasedModel for BottlerocketSetting<T> {
     ) -> Result<GenerateResult<serde_json::Value, serde_json::Value>, BottlerocketSettingError>
     {
         debug!(
-            existing_partial = existing_partial.as_ref().map(|v| v.to_string()),
-            dependent_settings = dependent_settings.as_ref().map(|v| v.to_string()),
+            existing_partial = existing_partial.as_ref().map(|v| self.redact(v).to_string()),
+            dependent_settings = ?dependent_settings.as_ref().map(setting_names),
             version = %T::get_version(),
             "Generating new value for setting."
         );
 
         let existing_partial = existing_partial
-            .map(|v| {
-                serde_json::from_value(v.clone()).context(error::DeserializeInputSnafu {
-                    version: T::get_version(),
-                    input: v,
-                    input_type: "existing-partial",
-                })
-            })
+            .map(|v| deserialize_input::<T, _>(v, "existing-partial"))
             .transpose()?;
         T::generate(existing_partial, dependent_settings)
             .map_err(Into::into)
@@ -172,25 +379,20 @@ impl<T: SettingsModel + 'static> TypeErasedModel for BottlerocketSetting<T> {
             })
     }
 
-    #[instrument(skip(self), err)]
+    #[instrument(skip_all, err)]
     fn validate(
         &self,
         value: serde_json::Value,
         validated_settings: Option<serde_json::Value>,
     ) -> Result<(), BottlerocketSettingError> {
         debug!(
-            %value,
-            validated_settings = validated_settings.as_ref().map(|v| v.to_string()),
+            value = %self.redact(&value),
+            validated_settings = ?validated_settings.as_ref().map(setting_names),
             version = %T::get_version(),
             "Validating value value for setting."
         );
 
-        let value =
-            serde_json::from_value(value.clone()).context(error::DeserializeInputSnafu {
-                version: T::get_version(),
-                input: value,
-                input_type: "value",
-            })?;
+        let value = deserialize_input::<T, _>(value, "value")?;
         T::validate(value, validated_settings)
             .map_err(Into::into)
             .context(error::ValidateSettingSnafu {
@@ -198,42 +400,39 @@ impl<T: SettingsModel + 'static> TypeErasedModel for BottlerocketSetting<T> {
             })
     }
 
+    fn template_helpers(
+        &self,
+    ) -> Result<HashMap<String, Box<dyn HelperDef>>, BottlerocketSettingError> {
+        T::template_helpers()
+            .map_err(Into::into)
+            .context(error::FetchTemplateHelpersSnafu)
+    }
+
     fn execute_template_helper(
         &self,
         helper_name: &str,
-        args: Vec<serde_json::Value>,
+        invocation: HelperInvocation,
     ) -> Result<serde_json::Value, BottlerocketSettingError> {
-        let all_helpers = T::template_helpers()
-            .map_err(Into::into)
-            .context(error::FetchTemplateHelpersSnafu)?;
-
-        let helper = all_helpers
-            .get(helper_name)
-            .context(error::FetchTemplateHelperSnafu {
-                helper_name: helper_name.to_string(),
-                helper_version: T::get_version(),
-            })?;
-
-        helper
-            .helper_fn(args)
-            .context(error::ExecuteTemplateHelperSnafu {
-                helper_name: helper_name.to_string(),
-                helper_version: T::get_version(),
-            })
+        execute_template_helper(
+            &self.template_helpers()?,
+            helper_name,
+            T::get_version(),
+            invocation,
+        )
     }
 
-    #[instrument(skip(self), err)]
+    #[instrument(skip_all, err)]
     fn parse_erased(
         &self,
         value: serde_json::Value,
     ) -> Result<Box<dyn Any>, BottlerocketSettingError> {
         debug!(
-            %value,
+            value = %self.redact(&value),
             version = %T::get_version(),
             "Parsing JSON value for setting."
         );
 
-        serde_json::from_value::<T>(value)
+        deserialize_redacted::<T>(value, &T::sensitive_fields())
             .context(error::ParseSettingSnafu {
                 version: T::get_version(),
             })
@@ -7,8 +7,11 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
+pub mod diff;
 #[doc(hidden)]
 pub mod erased;
+pub mod redact;
+pub use diff::SettingsDiff;
 pub use erased::{AsTypeErasedModel, TypeErasedModel};
 pub use error::BottlerocketSettingError;
 
@@ -82,6 +85,34 @@ pub trait SettingsModel: Sized + Serialize + DeserializeOwned + Debug {
     /// Returns an error if the value is rejected.
     fn set(current_value: Option<Self>, target: Self) -> Result<(), Self::ErrorKind>;
 
+    /// Determines whether this setting can be set to the `target` value, given its current value
+    /// and the [`SettingsDiff`] between the two.
+    ///
+    /// The diff is computed between the JSON representations of the parsed values, which saves
+    /// models from comparing the values field by field to enforce rules about which parts of a
+    /// setting may change.
+    ///
+    /// By default, this calls [`set`](SettingsModel::set), ignoring the diff.
+    fn set_with_diff(
+        current_value: Option<Self>,
+        target: Self,
+        _diff: &SettingsDiff,
+    ) -> Result<(), Self::ErrorKind> {
+        Self::set(current_value, target)
+    }
+
+    /// Determines whether this setting can be unset, given its current value.
+    ///
+    /// Returns an error if the removal is rejected, or [`UnsetResult::Replace`] with a value, such
+    /// as a regenerated default, which should take the place of the removed value. Replacement
+    /// values are checked with [`set_with_diff`](SettingsModel::set_with_diff), as any other new
+    /// value would be.
+    ///
+    /// By default, values may be removed.
+    fn unset(_current_value: Option<Self>) -> Result<UnsetResult<Self>, Self::ErrorKind> {
+        Ok(UnsetResult::Remove)
+    }
+
     /// Generates default values at system start.
     ///
     /// The settings system repeatedly invokes `generate` on all settings until they have
@@ -102,7 +133,39 @@ pub trait SettingsModel: Sized + Serialize + DeserializeOwned + Debug {
         _validated_settings: Option<serde_json::Value>,
     ) -> Result<(), Self::ErrorKind>;
 
+    /// Returns the paths to sensitive values within this settings model, such as credentials.
+    ///
+    /// Each path is a JSON pointer into the model's JSON representation, e.g. `"/password"`, in
+    /// which a `*` segment matches every member of an object or array. The empty pointer `""`
+    /// marks the entire value as sensitive. The SDK redacts these values from its log output and
+    /// errors; see the [`redact`] module.
+    fn sensitive_fields() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Returns the paths to values within this settings model which cannot change once the
+    /// setting has a value, such as identifiers assigned when a cluster is created.
+    ///
+    /// Paths are JSON pointers, as for [`sensitive_fields`](SettingsModel::sensitive_fields). The
+    /// SDK rejects any new value which adds, removes or changes a value at these paths relative to
+    /// the current value, before calling [`set_with_diff`](SettingsModel::set_with_diff).
+    fn immutable_fields() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Returns the paths to values within this settings model which can be given a value once,
+    /// such as a disk encryption choice, but cannot change afterwards.
+    ///
+    /// Unlike [`immutable_fields`](SettingsModel::immutable_fields), a write-once value which is
+    /// missing or `null` in the current value may still be filled in.
+    fn write_once_fields() -> Vec<&'static str> {
+        Vec::new()
+    }
+
     /// Returns the set of template helpers associated with this settings model.
+    ///
+    /// These take precedence over any helpers of the same name registered for all model versions
+    /// with [`SettingsExtensionBuilder::with_template_helpers`](crate::extension::SettingsExtensionBuilder::with_template_helpers).
     fn template_helpers() -> Result<HashMap<String, Box<dyn HelperDef>>, Self::ErrorKind> {
         Ok(HashMap::new())
     }
@@ -175,6 +238,27 @@ where
     }
 }
 
+/// The result of unsetting a setting value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum UnsetResult<T> {
+    /// Signals that the value can be removed.
+    Remove,
+
+    /// Signals that the value should be replaced with the given value rather than removed.
+    Replace(T),
+}
+
+/// An individual migration result from a batch migration, such as a flood migration.
+///
+/// The result owns its data, so it can be deserialized from the output of a settings extension.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct MigrationResult {
+    /// The version resulting from the migration.
+    pub version: String,
+    /// The value resulting from the migration.
+    pub value: serde_json::Value,
+}
+
 mod error {
     #![allow(missing_docs)]
     use crate::HelperError;
@@ -239,6 +323,16 @@ mod error {
             source: Box<dyn std::error::Error + Send + Sync + 'static>,
         },
 
+        #[snafu(display(
+            "Cannot change immutable field '{}' of setting version '{}'",
+            field,
+            version
+        ))]
+        ImmutableField {
+            version: &'static str,
+            field: String,
+        },
+
         #[snafu(display(
             "Failed to parse setting value (version '{}') from JSON: {}",
             version,
@@ -267,6 +361,12 @@ mod error {
             source: Box<dyn std::error::Error + Send + Sync + 'static>,
         },
 
+        #[snafu(display("Failed to run 'unset' on setting version '{}': {}", version, source))]
+        UnsetSetting {
+            version: &'static str,
+            source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        },
+
         #[snafu(display(
             "Failed to run 'validate' on setting version '{}': {}",
             version,
@@ -276,5 +376,15 @@ mod error {
             version: &'static str,
             source: Box<dyn std::error::Error + Send + Sync + 'static>,
         },
+
+        #[snafu(display(
+            "Cannot change write-once field '{}' of setting version '{}' once it has a value",
+            field,
+            version
+        ))]
+        WriteOnceField {
+            version: &'static str,
+            field: String,
+        },
     }
 }
@@ -0,0 +1,121 @@
+//! Provides redaction of sensitive setting values, such as credentials and tokens.
+//!
+//! Settings models declare their sensitive fields with
+//! [`SettingsModel::sensitive_fields`](super::SettingsModel::sensitive_fields), as a list of
+//! [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) into the model's JSON
+//! representation. A `*` path segment matches every member of an object or element of an array.
+//! The SDK replaces the values at these paths with [`REDACTED`] before they are logged or included
+//! in errors.
+//!
+//! ```
+//! use bottlerocket_settings_sdk::model::redact::{redact_value, REDACTED};
+//! use serde_json::json;
+//!
+//! let value = json!({"user": "admin", "password": "hunter2", "tokens": ["abc", "def"]});
+//! assert_eq!(
+//!     redact_value(&value, &["/password", "/tokens/*"]),
+//!     json!({"user": "admin", "password": REDACTED, "tokens": [REDACTED, REDACTED]})
+//! );
+//! ```
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// The marker which replaces sensitive values.
+pub const REDACTED: &str = "<redacted>";
+
+/// Returns a copy of the value with the values at each of the given JSON pointers replaced by
+/// [`REDACTED`].
+///
+/// The empty pointer `""` redacts the entire value. Pointers which do not refer to a value are
+/// ignored.
+pub fn redact_value(value: &Value, sensitive_fields: &[&str]) -> Value {
+    let mut value = value.clone();
+    sensitive_fields.iter().for_each(|pointer| {
+        for_each_at(&mut value, &segments(pointer), &mut |sensitive| {
+            *sensitive = Value::String(REDACTED.to_string());
+        })
+    });
+    value
+}
+
+/// Deserializes a value, redacting the values at the given JSON pointers from the error should it
+/// fail.
+///
+/// The error is reported as is unless it occurred at or within a sensitive value, in which case
+/// its message is replaced by [`REDACTED`] and the path of the offending value.
+pub fn deserialize_redacted<T: DeserializeOwned>(
+    value: Value,
+    sensitive_fields: &[&str],
+) -> Result<T, serde_json::Error> {
+    if sensitive_fields.is_empty() {
+        return serde_json::from_value(value);
+    }
+
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        if !is_sensitive_path(e.path(), sensitive_fields) {
+            return e.into_inner();
+        }
+        serde::de::Error::custom(format!("invalid value at '{}': {}", e.path(), REDACTED))
+    })
+}
+
+/// Returns whether a path into a value is at or within any of the given JSON pointers.
+///
+/// Path segments whose key is not known may match any pointer segment.
+fn is_sensitive_path(path: &serde_path_to_error::Path, sensitive_fields: &[&str]) -> bool {
+    use serde_path_to_error::Segment;
+
+    let path: Vec<Option<String>> = path
+        .iter()
+        .map(|segment| match segment {
+            Segment::Seq { index } => Some(index.to_string()),
+            Segment::Map { key } => Some(key.clone()),
+            Segment::Enum { variant } => Some(variant.clone()),
+            Segment::Unknown => None,
+        })
+        .collect();
+
+    sensitive_fields.iter().any(|pointer| {
+        let pointer = segments(pointer);
+        pointer.len() <= path.len()
+            && pointer.iter().zip(&path).all(|(expected, actual)| {
+                expected == "*" || actual.as_ref().is_none_or(|actual| actual == expected)
+            })
+    })
+}
+
+/// Splits a JSON pointer into its unescaped reference tokens.
+pub(super) fn segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Calls `f` with each value found by following the given path segments from `value`.
+fn for_each_at(value: &mut Value, segments: &[String], f: &mut impl FnMut(&mut Value)) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return f(value);
+    };
+
+    match (value, segment.as_str()) {
+        (Value::Object(members), "*") => members
+            .values_mut()
+            .for_each(|member| for_each_at(member, rest, f)),
+        (Value::Array(elements), "*") => elements
+            .iter_mut()
+            .for_each(|element| for_each_at(element, rest, f)),
+        (Value::Object(members), key) => {
+            if let Some(member) = members.get_mut(key) {
+                for_each_at(member, rest, f)
+            }
+        }
+        (Value::Array(elements), index) => {
+            if let Some(element) = index.parse().ok().and_then(|i: usize| elements.get_mut(i)) {
+                for_each_at(element, rest, f)
+            }
+        }
+        _ => {}
+    }
+}
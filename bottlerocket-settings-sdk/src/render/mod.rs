@@ -0,0 +1,184 @@
+//! Provides rendering of Handlebars config templates using a settings model's template helpers.
+//!
+//! Bottlerocket renders config files from Handlebars templates, which refer to settings values
+//! under the `settings` key and may call helpers provided by settings extensions. This module
+//! reproduces that environment so that extensions can check their config file output in their own
+//! test suites.
+//!
+//! # Examples
+//!
+//! ```
+//! use bottlerocket_settings_sdk::render::{render_template, settings_context};
+//! use bottlerocket_settings_sdk::{provide_template_helpers, template_helper};
+//! use serde_json::json;
+//!
+//! #[template_helper(ident = exclaim_helper)]
+//! fn exclaim(s: String) -> Result<String, anyhow::Error> {
+//!     Ok(format!("{}!", s))
+//! }
+//!
+//! let context = settings_context("motd", json!("Hello"), None).unwrap();
+//! let rendered = render_template(
+//!     "{{exclaim settings.motd}}",
+//!     &context,
+//!     provide_template_helpers! { "exclaim" => exclaim_helper },
+//!     None,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(rendered, "Hello!");
+//! ```
+use crate::helper::{HelperContext, HelperDef, HelperInvocation};
+use handlebars::{
+    Context, Handlebars, Helper, RenderContext, RenderError, RenderErrorReason, ScopedJson,
+};
+use snafu::{ensure, ResultExt};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tracing::debug;
+
+pub use error::TemplateRenderError;
+
+type Result<T> = std::result::Result<T, TemplateRenderError>;
+
+/// Builds the context used to render a template for the setting with the given name and value.
+///
+/// The setting is made available at `settings.<name>`. Any `required_settings`, given as a JSON
+/// object keyed by setting name, are made available alongside it.
+pub fn settings_context(
+    name: &str,
+    value: serde_json::Value,
+    required_settings: Option<serde_json::Value>,
+) -> Result<serde_json::Value> {
+    let mut settings = match required_settings {
+        None => serde_json::Map::new(),
+        Some(serde_json::Value::Object(settings)) => settings,
+        // The value may contain other settings, including sensitive ones, so only its type is
+        // reported.
+        Some(other) => {
+            return error::RequiredSettingsTypeSnafu {
+                json_type: json_type_name(&other),
+            }
+            .fail()
+        }
+    };
+    settings.insert(name.to_string(), value);
+
+    Ok(serde_json::json!({ "settings": settings }))
+}
+
+/// Returns the name of the JSON type of a value, e.g. "array".
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Renders a Handlebars template with the given context, using the given template helpers.
+///
+/// Helpers which use a [`HelperContext`] receive the given `helper_context`.
+///
+/// Rendering is strict: referring to a missing value is an error. Output is not HTML-escaped.
+pub fn render_template(
+    template: &str,
+    context: &serde_json::Value,
+    helpers: HashMap<String, Box<dyn HelperDef>>,
+    helper_context: Option<HelperContext>,
+) -> Result<String> {
+    ensure!(context.is_object(), error::ContextTypeSnafu);
+
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(handlebars::no_escape);
+    helpers.keys().for_each(|name| {
+        debug!(name, "Registering template helper.");
+        registry.register_helper(
+            name,
+            Box::new(HandlebarsHelper {
+                name: name.clone(),
+                helper_context: helper_context.clone(),
+            }),
+        );
+    });
+
+    // Restore the previous helpers once rendered, in case a helper renders a template itself.
+    let previous_helpers = RENDER_HELPERS.with(|h| h.replace(Rc::new(helpers)));
+    let rendered = registry.render_template(template, context);
+    RENDER_HELPERS.with(|h| h.replace(previous_helpers));
+
+    rendered.context(error::RenderTemplateSnafu)
+}
+
+thread_local! {
+    /// The helpers available to the template being rendered on this thread.
+    ///
+    /// Handlebars requires its helpers to be `Send` and `Sync`, which settings extension helpers
+    /// need not be. Rendering is synchronous, so each helper is instead registered as a
+    /// [`HandlebarsHelper`] which looks it up here by name.
+    static RENDER_HELPERS: RefCell<Rc<HashMap<String, Box<dyn HelperDef>>>> = RefCell::default();
+}
+
+/// Adapts a settings extension [`HelperDef`] into a Handlebars helper.
+///
+/// Positional and hash parameters are passed to the helper as its positional and named arguments.
+struct HandlebarsHelper {
+    name: String,
+    helper_context: Option<HelperContext>,
+}
+
+impl handlebars::HelperDef for HandlebarsHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> std::result::Result<ScopedJson<'rc>, RenderError> {
+        let invocation = HelperInvocation {
+            args: h.params().iter().map(|p| p.value().clone()).collect(),
+            hash: h
+                .hash()
+                .iter()
+                .map(|(name, p)| (name.to_string(), p.value().clone()))
+                .collect(),
+            context: self.helper_context.clone(),
+        };
+
+        let helpers = RENDER_HELPERS.with(|h| Rc::clone(&h.borrow()));
+        let helper = helpers.get(&self.name).ok_or_else(|| {
+            RenderErrorReason::Other(format!("Helper '{}' is not available", self.name))
+        })?;
+
+        helper
+            .execute(invocation)
+            .map(ScopedJson::Derived)
+            .map_err(|e| {
+                RenderErrorReason::Other(format!("Helper '{}' failed: {}", self.name, e)).into()
+            })
+    }
+}
+
+mod error {
+    #![allow(missing_docs)]
+    use snafu::Snafu;
+
+    /// The error type returned when rendering a template.
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility(pub))]
+    pub enum TemplateRenderError {
+        #[snafu(display("Template context must be a JSON object"))]
+        ContextType,
+
+        #[snafu(display("Failed to render template: {}", source))]
+        RenderTemplate { source: handlebars::RenderError },
+
+        #[snafu(display("Required settings must be a JSON object, got a JSON {}", json_type))]
+        RequiredSettingsType { json_type: &'static str },
+    }
+}
@@ -0,0 +1,396 @@
+//! Provides the [`GenerationSimulator`], which reproduces the settings system's repeated
+//! `generate` cycles locally.
+//!
+//! During boot, the settings system repeatedly invokes `generate` on every setting until all of them
+//! are complete. On each cycle, settings which returned [`GenerateResult::NeedsData`] are handed
+//! their previous partial value, as well as the settings which have been generated so far. This only
+//! makes sense when several extensions feed each other, which is what the simulator models.
+//!
+//! Extensions can be simulated in-process with [`InProcessExtension`], or as binaries with
+//! [`BinaryExtension`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use bottlerocket_settings_sdk::example::empty::EmptySetting;
+//! # use bottlerocket_settings_sdk::{BottlerocketSetting, LinearMigratorExtensionBuilder};
+//! use bottlerocket_settings_sdk::simulator::{
+//!     GenerationSimulatorBuilder, InProcessExtension, SimulationOutcome,
+//! };
+//!
+//! let extension = LinearMigratorExtensionBuilder::with_name("empty")
+//!     .with_models(vec![BottlerocketSetting::<EmptySetting>::model()])
+//!     .build()
+//!     .unwrap();
+//!
+//! let report = GenerationSimulatorBuilder::new()
+//!     .with_extension(InProcessExtension::new(&extension, "v1"))
+//!     .build()
+//!     .unwrap()
+//!     .run();
+//!
+//! assert_eq!(report.outcome, SimulationOutcome::Complete);
+//! assert_eq!(report.rounds.len(), 1);
+//! ```
+use crate::client::ExtensionClient;
+use crate::model::erased::AsTypeErasedModel;
+use crate::{GenerateResult, Migrator, SettingsExtension};
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::{debug, info};
+
+pub use error::SimulatorError;
+
+/// The error type returned by simulated extensions.
+pub type SimulationError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The default maximum number of generation rounds to simulate.
+pub const DEFAULT_MAX_ROUNDS: usize = 100;
+
+/// A settings extension which can take part in a generation simulation.
+pub trait SimulatedExtension {
+    /// Returns the name of the setting generated by the extension.
+    fn name(&self) -> &str;
+
+    /// Runs a single `generate` cycle for the extension.
+    fn generate(
+        &self,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<serde_json::Value, serde_json::Value>, SimulationError>;
+}
+
+/// Simulates a [`SettingsExtension`] in-process, using one of its model versions.
+pub struct InProcessExtension<'a, Mi, Mo>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    extension: &'a SettingsExtension<Mi, Mo>,
+    setting_version: String,
+}
+
+impl<'a, Mi, Mo> InProcessExtension<'a, Mi, Mo>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    /// Simulates the given extension using the model with the given version.
+    pub fn new(extension: &'a SettingsExtension<Mi, Mo>, setting_version: &str) -> Self {
+        Self {
+            extension,
+            setting_version: setting_version.to_string(),
+        }
+    }
+}
+
+impl<Mi, Mo> SimulatedExtension for InProcessExtension<'_, Mi, Mo>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    fn name(&self) -> &str {
+        self.extension.name()
+    }
+
+    fn generate(
+        &self,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<serde_json::Value, serde_json::Value>, SimulationError> {
+        let model = self.extension.model(&self.setting_version).ok_or_else(|| {
+            format!(
+                "Requested model version '{}' not found",
+                self.setting_version
+            )
+        })?;
+
+        Ok(model
+            .as_model()
+            .generate(existing_partial, required_settings)?)
+    }
+}
+
+/// Simulates a settings extension binary, invoking it with an [`ExtensionClient`].
+#[derive(Debug, Clone)]
+pub struct BinaryExtension {
+    name: String,
+    setting_version: String,
+    client: ExtensionClient,
+}
+
+impl BinaryExtension {
+    /// Simulates the setting with the given name, using the given extension client and version.
+    pub fn new(name: &str, setting_version: &str, client: ExtensionClient) -> Self {
+        Self {
+            name: name.to_string(),
+            setting_version: setting_version.to_string(),
+            client,
+        }
+    }
+}
+
+impl SimulatedExtension for BinaryExtension {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn generate(
+        &self,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<serde_json::Value, serde_json::Value>, SimulationError> {
+        Ok(self
+            .client
+            .generate(&self.setting_version, existing_partial, required_settings)?)
+    }
+}
+
+/// Runs generation rounds for a set of settings extensions until all of them complete.
+///
+/// On each round, every incomplete extension is given its partial value from the previous round,
+/// as well as a JSON object containing every setting that was complete at the start of the round,
+/// keyed by setting name.
+///
+/// Simulators are created with a [`GenerationSimulatorBuilder`].
+pub struct GenerationSimulator<'a> {
+    extensions: Vec<Box<dyn SimulatedExtension + 'a>>,
+    max_rounds: usize,
+}
+
+/// Builds a [`GenerationSimulator`].
+pub struct GenerationSimulatorBuilder<'a> {
+    extensions: Vec<Box<dyn SimulatedExtension + 'a>>,
+    max_rounds: usize,
+}
+
+impl Default for GenerationSimulatorBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> GenerationSimulatorBuilder<'a> {
+    /// Creates a builder for a simulator with no extensions.
+    pub fn new() -> Self {
+        Self {
+            extensions: Vec::new(),
+            max_rounds: DEFAULT_MAX_ROUNDS,
+        }
+    }
+
+    /// Adds an extension to the simulation.
+    pub fn with_extension<E: SimulatedExtension + 'a>(mut self, extension: E) -> Self {
+        self.extensions.push(Box::new(extension));
+        self
+    }
+
+    /// Sets the maximum number of rounds to simulate before giving up.
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Builds the simulator.
+    ///
+    /// Settings are keyed by name, so each extension must generate a setting with a distinct name.
+    pub fn build(self) -> Result<GenerationSimulator<'a>, SimulatorError> {
+        let mut names = BTreeSet::new();
+        for extension in &self.extensions {
+            ensure!(
+                names.insert(extension.name()),
+                error::DuplicateExtensionSnafu {
+                    name: extension.name()
+                }
+            );
+        }
+
+        Ok(GenerationSimulator {
+            extensions: self.extensions,
+            max_rounds: self.max_rounds,
+        })
+    }
+}
+
+impl GenerationSimulator<'_> {
+    /// Runs generation rounds until every extension completes, no progress is made, the settings
+    /// return to a state from an earlier round, an extension fails, or the maximum number of rounds
+    /// is reached.
+    pub fn run(&self) -> SimulationReport {
+        let mut partials: BTreeMap<String, Option<serde_json::Value>> = BTreeMap::new();
+        let mut settings: BTreeMap<String, serde_json::Value> = BTreeMap::new();
+        let mut rounds = Vec::new();
+        // The state of the simulation at the end of each round, mapped to that round's number.
+        let mut seen_states = HashMap::new();
+
+        let outcome = loop {
+            if settings.len() == self.extensions.len() {
+                break SimulationOutcome::Complete;
+            }
+            if rounds.len() == self.max_rounds {
+                break SimulationOutcome::RoundLimit {
+                    incomplete: self.incomplete(&settings),
+                };
+            }
+
+            let round_number = rounds.len() + 1;
+            debug!(round = round_number, "Starting generation round.");
+            let required_settings = serde_json::to_value(&settings)
+                .expect("Failed to serialize map of JSON values as JSON");
+
+            let mut round = GenerationRound {
+                completed: Vec::new(),
+                needs_data: Vec::new(),
+            };
+            let mut progressed = false;
+            let mut failure = None;
+
+            for extension in &self.extensions {
+                let name = extension.name().to_string();
+                if settings.contains_key(&name) {
+                    continue;
+                }
+
+                let previous_partial = partials.get(&name).cloned().flatten();
+                match extension.generate(previous_partial.clone(), Some(required_settings.clone()))
+                {
+                    Ok(GenerateResult::Complete(value)) => {
+                        debug!(round = round_number, name, "Setting generation complete.");
+                        settings.insert(name.clone(), value);
+                        round.completed.push(name);
+                        progressed = true;
+                    }
+                    Ok(GenerateResult::NeedsData(partial)) => {
+                        debug!(round = round_number, name, "Setting needs more data.");
+                        if !partials.contains_key(&name) || partial != previous_partial {
+                            progressed = true;
+                        }
+                        partials.insert(name.clone(), partial);
+                        round.needs_data.push(name);
+                    }
+                    Err(e) => {
+                        failure = Some(SimulationOutcome::Failed {
+                            extension: name,
+                            message: e.to_string(),
+                        });
+                        break;
+                    }
+                }
+            }
+
+            rounds.push(round);
+
+            if let Some(failure) = failure {
+                break failure;
+            }
+            if !progressed {
+                break SimulationOutcome::Livelock {
+                    incomplete: self.incomplete(&settings),
+                };
+            }
+
+            let state = serde_json::to_string(&(&partials, &settings))
+                .expect("Failed to serialize maps of JSON values as JSON");
+            if let Some(previous_round) = seen_states.insert(state, round_number) {
+                break SimulationOutcome::Cycle {
+                    incomplete: self.incomplete(&settings),
+                    period: round_number - previous_round,
+                };
+            }
+        };
+
+        info!(
+            rounds = rounds.len(),
+            ?outcome,
+            "Generation simulation finished."
+        );
+
+        partials.retain(|name, _| !settings.contains_key(name));
+
+        SimulationReport {
+            outcome,
+            rounds,
+            settings,
+            partials,
+        }
+    }
+
+    /// Returns the names of all extensions which have not completed.
+    fn incomplete(&self, settings: &BTreeMap<String, serde_json::Value>) -> Vec<String> {
+        self.extensions
+            .iter()
+            .map(|extension| extension.name().to_string())
+            .filter(|name| !settings.contains_key(name))
+            .collect()
+    }
+}
+
+/// The report produced by a generation simulation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    /// How the simulation ended.
+    pub outcome: SimulationOutcome,
+    /// Each generation round that was run, in order.
+    pub rounds: Vec<GenerationRound>,
+    /// The final settings tree, containing every completed setting keyed by name.
+    pub settings: BTreeMap<String, serde_json::Value>,
+    /// The last partial value of every setting which did not complete.
+    pub partials: BTreeMap<String, Option<serde_json::Value>>,
+}
+
+/// The settings which changed state during a single generation round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerationRound {
+    /// Settings which completed during this round.
+    pub completed: Vec<String>,
+    /// Settings which requested more data during this round.
+    pub needs_data: Vec<String>,
+}
+
+/// How a generation simulation ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimulationOutcome {
+    /// Every setting completed.
+    Complete,
+    /// A round completed without any setting completing or changing its partial value, so further
+    /// rounds would not make progress.
+    Livelock {
+        /// The settings which did not complete.
+        incomplete: Vec<String>,
+    },
+    /// A round ended with the same partial values and settings as an earlier round, so further
+    /// rounds would repeat the same states forever.
+    Cycle {
+        /// The settings which did not complete.
+        incomplete: Vec<String>,
+        /// The number of rounds after which the states repeat.
+        period: usize,
+    },
+    /// The maximum number of rounds was reached while settings were still making progress.
+    RoundLimit {
+        /// The settings which did not complete.
+        incomplete: Vec<String>,
+    },
+    /// An extension returned an error during generation.
+    Failed {
+        /// The name of the failed extension.
+        extension: String,
+        /// The error returned by the extension.
+        message: String,
+    },
+}
+
+mod error {
+    #![allow(missing_docs)]
+    use snafu::Snafu;
+
+    /// The error type returned when building a
+    /// [`GenerationSimulator`](super::GenerationSimulator).
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility(pub))]
+    pub enum SimulatorError {
+        #[snafu(display("More than one extension generates the setting '{}'", name))]
+        DuplicateExtension { name: String },
+    }
+}
@@ -0,0 +1,66 @@
+//! Provides [`Fixtures`], which loads JSON test fixtures for settings extensions.
+use crate::SettingsModel;
+use std::path::{Path, PathBuf};
+
+/// Loads JSON settings values from a directory of fixtures.
+///
+/// Fixtures are organized by setting version, with each fixture stored at
+/// `<root>/<version>/<name>.json`, e.g.:
+///
+/// ```text
+/// tests/fixtures/
+/// ├── v1
+/// │   └── greeting.json
+/// └── v2
+///     └── greeting.json
+/// ```
+///
+/// Fixtures are intended for use in tests, so loading a missing or malformed fixture panics with a
+/// description of the problem.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    root: PathBuf,
+}
+
+impl Fixtures {
+    /// Creates a fixture loader rooted at the given directory.
+    ///
+    /// Relative paths are resolved against the current directory, which is the package root when
+    /// running `cargo test`.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the path of a fixture for the given setting version.
+    pub fn path(&self, setting_version: &str, name: &str) -> PathBuf {
+        self.root.join(setting_version).join(format!("{name}.json"))
+    }
+
+    /// Loads a fixture for the given setting version as a JSON value.
+    pub fn value(&self, setting_version: &str, name: &str) -> serde_json::Value {
+        let path = self.path(setting_version, name);
+        let contents = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read fixture '{}': {e}", path.display()));
+
+        serde_json::from_str(&contents)
+            .unwrap_or_else(|e| panic!("Failed to parse fixture '{}' as JSON: {e}", path.display()))
+    }
+
+    /// Loads a fixture as the given settings model, using the model's version.
+    pub fn model<T: SettingsModel>(&self, name: &str) -> T {
+        let path = self.path(T::get_version(), name);
+
+        serde_json::from_value(self.value(T::get_version(), name)).unwrap_or_else(|e| {
+            panic!(
+                "Failed to parse fixture '{}' as settings version '{}': {e}",
+                path.display(),
+                T::get_version()
+            )
+        })
+    }
+
+    /// Returns the root directory of the fixtures.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
@@ -0,0 +1,314 @@
+//! Provides [`ExtensionHarness`], which runs CLI-level tests against a settings extension.
+//!
+//! # Examples
+//!
+//! ```
+//! # use bottlerocket_settings_sdk::example::empty::EmptySetting;
+//! # use bottlerocket_settings_sdk::{BottlerocketSetting, LinearMigratorExtensionBuilder};
+//! use bottlerocket_settings_sdk::assert_extension_error;
+//! use bottlerocket_settings_sdk::testing::ExtensionHarness;
+//! use serde_json::json;
+//!
+//! let harness = ExtensionHarness::new(|| {
+//!     LinearMigratorExtensionBuilder::with_name("example")
+//!         .with_models(vec![BottlerocketSetting::<EmptySetting>::model()])
+//!         .build()
+//!         .unwrap()
+//! });
+//!
+//! harness.set("v1", json!(null)).unwrap();
+//! assert_extension_error!(harness.set("v2", json!(null)), NoSuchModel);
+//! ```
+use crate::cli::proto1::Proto1Command;
+use crate::extension::SettingsExtensionError;
+use crate::helper::{HelperInvocation, HelperSignature};
+use crate::model::erased::AsTypeErasedModel;
+use crate::{
+    GenerateResult, MigrationResult, Migrator, SettingsDiff, SettingsExtension, UnsetResult,
+};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+
+/// Runs proto1 commands against a settings extension through its CLI interface, using
+/// [`SettingsExtension::try_run_with_args`].
+///
+/// Running a command consumes the extension, so the harness is constructed with a function which
+/// builds a fresh extension for each command.
+///
+/// Each helper returns the extension's [`SettingsExtensionError`] on failure, which can be checked
+/// with [`assert_extension_error!`](crate::assert_extension_error). Successful output is parsed
+/// into the SDK's own types; the harness panics if the output cannot be parsed.
+pub struct ExtensionHarness<F> {
+    build_extension: F,
+}
+
+impl<F, Mi, Mo> ExtensionHarness<F>
+where
+    F: Fn() -> SettingsExtension<Mi, Mo>,
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    /// Creates a harness which builds an extension for each command with the given function.
+    pub fn new(build_extension: F) -> Self {
+        Self { build_extension }
+    }
+
+    /// Runs a proto1 command against a freshly built extension, returning its raw output.
+    pub fn run(
+        &self,
+        command: Proto1Command,
+    ) -> Result<String, SettingsExtensionError<Mi::ErrorKind>> {
+        let args = ["extension", "proto1"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(command.to_args());
+
+        (self.build_extension)().try_run_with_args(args)
+    }
+
+    /// Runs the `set` command with no current value.
+    pub fn set(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::set(setting_version, value, None))
+            .map(|_| ())
+    }
+
+    /// Runs the `set` command with the given current value.
+    pub fn set_with_current(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: serde_json::Value,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::set(
+            setting_version,
+            value,
+            Some(current_value),
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `set` command with new and current values given in `setting_version`, which are
+    /// migrated to `target_version` before being checked.
+    pub fn set_with_target_version(
+        &self,
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::set_with_target_version(
+            setting_version,
+            target_version,
+            value,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `set` command with a JSON merge patch against the given current value.
+    pub fn set_merge_patch(
+        &self,
+        setting_version: &str,
+        merge_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::set_merge_patch(
+            setting_version,
+            merge_patch,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `set` command with a JSON patch against the given current value.
+    pub fn set_json_patch(
+        &self,
+        setting_version: &str,
+        json_patch: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::set_json_patch(
+            setting_version,
+            json_patch,
+            current_value,
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `unset` command with the given current value, parsing the result.
+    pub fn unset<T: DeserializeOwned>(
+        &self,
+        setting_version: &str,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<UnsetResult<T>, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run_parsed(Proto1Command::unset(setting_version, current_value))
+    }
+
+    /// Runs the `diff` command against the given current value, parsing the result.
+    pub fn diff(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        current_value: Option<serde_json::Value>,
+    ) -> Result<SettingsDiff, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run_parsed(Proto1Command::diff(setting_version, value, current_value))
+    }
+
+    /// Runs the `generate` command, parsing the result.
+    pub fn generate<P, C>(
+        &self,
+        setting_version: &str,
+        existing_partial: Option<serde_json::Value>,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<P, C>, SettingsExtensionError<Mi::ErrorKind>>
+    where
+        P: DeserializeOwned,
+        C: DeserializeOwned,
+    {
+        self.run_parsed(Proto1Command::generate(
+            setting_version,
+            existing_partial,
+            required_settings,
+        ))
+    }
+
+    /// Runs the `validate` command.
+    pub fn validate(
+        &self,
+        setting_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::validate(
+            setting_version,
+            value,
+            required_settings,
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `validate` command with a value given in `setting_version`, which is migrated to
+    /// `target_version` before being validated.
+    pub fn validate_with_target_version(
+        &self,
+        setting_version: &str,
+        target_version: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<(), SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::validate_with_target_version(
+            setting_version,
+            target_version,
+            value,
+            required_settings,
+        ))
+        .map(|_| ())
+    }
+
+    /// Runs the `migrate` command, parsing the migrated value.
+    pub fn migrate(
+        &self,
+        value: serde_json::Value,
+        from_version: &str,
+        target_version: &str,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run_parsed(Proto1Command::migrate(value, from_version, target_version))
+    }
+
+    /// Runs the `flood-migrate` command, parsing the migration results.
+    pub fn flood_migrate(
+        &self,
+        value: serde_json::Value,
+        from_version: &str,
+    ) -> Result<Vec<MigrationResult>, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run_parsed(Proto1Command::flood_migrate(value, from_version))
+    }
+
+    /// Runs the `helper` command, parsing the helper's output.
+    ///
+    /// Arguments may be given as a list of positional arguments, or as a [`HelperInvocation`] which
+    /// also contains named arguments and the context of the setting. The context's version is
+    /// ignored in favor of `setting_version`.
+    pub fn template_helper<I: Into<HelperInvocation>>(
+        &self,
+        setting_version: &str,
+        helper_name: &str,
+        invocation: I,
+    ) -> Result<serde_json::Value, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run_parsed(Proto1Command::helper(
+            setting_version,
+            helper_name,
+            invocation,
+        ))
+    }
+
+    /// Runs the `helpers` command, parsing the signatures of the available template helpers.
+    ///
+    /// Helpers which do not describe their signature are mapped to `None`.
+    pub fn list_helpers(
+        &self,
+        setting_version: &str,
+    ) -> Result<BTreeMap<String, Option<HelperSignature>>, SettingsExtensionError<Mi::ErrorKind>>
+    {
+        self.run_parsed(Proto1Command::list_helpers(setting_version))
+    }
+
+    /// Runs the `render` command, returning the rendered template.
+    #[cfg(feature = "render")]
+    pub fn render(
+        &self,
+        setting_version: &str,
+        template: &str,
+        value: serde_json::Value,
+        required_settings: Option<serde_json::Value>,
+    ) -> Result<String, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(Proto1Command::render(
+            setting_version,
+            template,
+            value,
+            required_settings,
+        ))
+    }
+
+    /// Runs a proto1 command, parsing its output as JSON.
+    fn run_parsed<T: DeserializeOwned>(
+        &self,
+        command: Proto1Command,
+    ) -> Result<T, SettingsExtensionError<Mi::ErrorKind>> {
+        self.run(command).map(|output| {
+            serde_json::from_str(&output).unwrap_or_else(|e| {
+                panic!("Failed to parse settings extension output '{output}' as JSON: {e}")
+            })
+        })
+    }
+}
+
+#[macro_export]
+/// Asserts that a result is an `Err` containing the given
+/// [`SettingsExtensionError`](crate::extension::SettingsExtensionError) variant.
+///
+/// ```
+/// # use bottlerocket_settings_sdk::assert_extension_error;
+/// # use bottlerocket_settings_sdk::extension::SettingsExtensionError;
+/// # use bottlerocket_settings_sdk::migrate::null::NullMigratorError;
+/// let result: Result<(), SettingsExtensionError<NullMigratorError>> =
+///     Err(SettingsExtensionError::ParseCLICommand);
+///
+/// assert_extension_error!(result, ParseCLICommand);
+/// ```
+macro_rules! assert_extension_error {
+    ($result:expr, $variant:ident) => {
+        match $result {
+            Err($crate::extension::SettingsExtensionError::$variant { .. }) => {}
+            other => panic!(
+                "Expected settings extension error '{}', got: {:?}",
+                stringify!($variant),
+                other
+            ),
+        }
+    };
+}
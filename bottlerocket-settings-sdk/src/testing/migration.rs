@@ -0,0 +1,414 @@
+//! Provides [`MigrationRoundTrip`], which checks that an extension's migrations round-trip values
+//! between versions.
+//!
+//! # Examples
+//!
+//! ```
+//! # use bottlerocket_settings_sdk::example::empty::EmptySetting;
+//! # use bottlerocket_settings_sdk::{BottlerocketSetting, LinearMigratorExtensionBuilder};
+//! use bottlerocket_settings_sdk::testing::MigrationRoundTrip;
+//!
+//! let extension = LinearMigratorExtensionBuilder::with_name("example")
+//!     .with_models(vec![BottlerocketSetting::<EmptySetting>::model()])
+//!     .build()
+//!     .unwrap();
+//!
+//! MigrationRoundTrip::new(&extension)
+//!     .with_sample(EmptySetting)
+//!     .with_generated_samples()
+//!     .assert_round_trips();
+//! ```
+use crate::migrate::{Migrator, PreservedData};
+use crate::model::erased::AsTypeErasedModel;
+use crate::{GenerateResult, SettingsExtension, SettingsModel};
+use std::collections::HashMap;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Checks that migrations between all versions of a settings extension round-trip values.
+///
+/// For each sample value, [`MigrationRoundTrip`] asserts that:
+/// * Migrating the value to every other version and back results in the original value.
+/// * Flood migrating the value, then migrating each result back, results in the original value.
+/// * Flood migration results agree with target migrations to the same version.
+///
+/// Round trips use [`Migrator::perform_preserving_migration`], so data which a migrator preserves
+/// is expected to be restored. Flood migrations don't return preserved data, so each flood result
+/// is migrated back using the data preserved by a targeted migration to the same version, which
+/// the flood result must also agree with. Fields which are known to be lost can be declared with
+/// [`with_lossy_field`](Self::with_lossy_field), in which case they are ignored when comparing
+/// values.
+pub struct MigrationRoundTrip<'a, Mi, Mo>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    extension: &'a SettingsExtension<Mi, Mo>,
+    samples: Vec<(String, serde_json::Value)>,
+    lossy_fields: HashMap<String, Vec<String>>,
+    generated_samples: bool,
+}
+
+impl<'a, Mi, Mo> MigrationRoundTrip<'a, Mi, Mo>
+where
+    Mo: AsTypeErasedModel,
+    Mi: Migrator<ModelKind = Mo>,
+{
+    /// Creates a new round-trip check for the given settings extension.
+    pub fn new(extension: &'a SettingsExtension<Mi, Mo>) -> Self {
+        Self {
+            extension,
+            samples: Vec::new(),
+            lossy_fields: HashMap::new(),
+            generated_samples: false,
+        }
+    }
+
+    /// Adds a sample value to check, using the version of its settings model.
+    pub fn with_sample<T: SettingsModel>(mut self, sample: T) -> Self {
+        let value = serde_json::to_value(&sample).expect("Failed to serialize sample value");
+        self.samples.push((T::get_version().to_string(), value));
+        self
+    }
+
+    /// Adds a list of sample values to check, using the version of their settings model.
+    pub fn with_samples<T, I>(self, samples: I) -> Self
+    where
+        T: SettingsModel,
+        I: IntoIterator<Item = T>,
+    {
+        samples
+            .into_iter()
+            .fold(self, |round_trip, sample| round_trip.with_sample(sample))
+    }
+
+    /// Adds a sample value for each version by calling the model's `generate` with no inputs.
+    ///
+    /// Versions whose generation does not complete on its own are skipped.
+    pub fn with_generated_samples(mut self) -> Self {
+        self.generated_samples = true;
+        self
+    }
+
+    /// Declares that a field of the given version may be lost when migrating through other
+    /// versions.
+    ///
+    /// `pointer` is a JSON pointer (RFC 6901) into values of `version`, e.g. `/nickname`.
+    pub fn with_lossy_field(mut self, version: &str, pointer: &str) -> Self {
+        self.lossy_fields
+            .entry(version.to_string())
+            .or_default()
+            .push(pointer.to_string());
+        self
+    }
+
+    /// Runs all round-trip checks, returning every failure found.
+    pub fn check(&self) -> Vec<RoundTripFailure> {
+        let mut versions: Vec<&str> = self
+            .extension
+            .iter_models()
+            .map(|(version, _)| version)
+            .collect();
+        versions.sort();
+
+        self.all_samples()
+            .into_iter()
+            .flat_map(|(version, value)| {
+                let mut failures: Vec<RoundTripFailure> = versions
+                    .iter()
+                    .filter(|&&other| other != version)
+                    .filter_map(|other| {
+                        self.check_target_round_trip(&version, other, &value)
+                            .err()
+                            .map(|reason| RoundTripFailure {
+                                sample_version: version.clone(),
+                                via_version: other.to_string(),
+                                kind: RoundTripKind::Target,
+                                reason,
+                            })
+                    })
+                    .collect();
+                failures.extend(self.check_flood_round_trip(&version, &value));
+                failures
+            })
+            .collect()
+    }
+
+    /// Runs all round-trip checks, panicking with a description of every failure found.
+    pub fn assert_round_trips(&self) {
+        let failures = self.check();
+        if !failures.is_empty() {
+            let descriptions: Vec<String> = failures.iter().map(ToString::to_string).collect();
+            panic!(
+                "{} migration round trip(s) failed:\n{}",
+                failures.len(),
+                descriptions.join("\n")
+            );
+        }
+    }
+
+    /// Returns the given samples, as well as any generated samples.
+    fn all_samples(&self) -> Vec<(String, serde_json::Value)> {
+        let mut samples = self.samples.clone();
+
+        if self.generated_samples {
+            let mut generated: Vec<(String, serde_json::Value)> = self
+                .extension
+                .iter_models()
+                .filter_map(
+                    |(version, model)| match model.as_model().generate(None, None) {
+                        Ok(GenerateResult::Complete(value)) => Some((version.to_string(), value)),
+                        _ => {
+                            debug!(version, "Skipping generated sample for version.");
+                            None
+                        }
+                    },
+                )
+                .collect();
+            generated.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+            samples.extend(generated);
+        }
+
+        samples
+    }
+
+    /// Migrates a sample value to another version and back again.
+    fn check_target_round_trip(
+        &self,
+        version: &str,
+        other: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), RoundTripFailureReason> {
+        let (there, preserved_data) =
+            self.migrate(value.clone(), version, other, PreservedData::new())?;
+        let (back, _) = self.migrate(there, other, version, preserved_data)?;
+
+        self.compare(version, value, back)
+    }
+
+    /// Flood migrates a sample value, then migrates each result back to the sample's version.
+    fn check_flood_round_trip(
+        &self,
+        version: &str,
+        value: &serde_json::Value,
+    ) -> Vec<RoundTripFailure> {
+        let failure = |via_version: &str, reason| RoundTripFailure {
+            sample_version: version.to_string(),
+            via_version: via_version.to_string(),
+            kind: RoundTripKind::Flood,
+            reason,
+        };
+
+        let flood_results = match self.parse(version, value.clone()).and_then(|parsed| {
+            self.extension
+                .migrator()
+                .perform_flood_migrations(self.extension, parsed, version)
+                .map_err(|e| RoundTripFailureReason::MigrationFailed(e.to_string()))
+        }) {
+            Ok(results) => results,
+            Err(reason) => return vec![failure(version, reason)],
+        };
+
+        flood_results
+            .into_iter()
+            .filter_map(|result| {
+                let check = || {
+                    // Flood migrations don't return preserved data, so the data preserved by a
+                    // targeted migration to the same version is used for the return trip.
+                    let preserved_data = if result.version != version {
+                        let (targeted, preserved_data) = self.migrate(
+                            value.clone(),
+                            version,
+                            &result.version,
+                            PreservedData::new(),
+                        )?;
+                        if targeted != result.value {
+                            return Err(RoundTripFailureReason::FloodMismatch {
+                                flood: result.value.clone(),
+                                target: targeted,
+                            });
+                        }
+                        preserved_data
+                    } else {
+                        PreservedData::new()
+                    };
+
+                    let (back, _) = self.migrate(
+                        result.value.clone(),
+                        &result.version,
+                        version,
+                        preserved_data,
+                    )?;
+                    self.compare(version, value, back)
+                };
+
+                check().err().map(|reason| failure(&result.version, reason))
+            })
+            .collect()
+    }
+
+    /// Performs a single preserving migration on a JSON value.
+    fn migrate(
+        &self,
+        value: serde_json::Value,
+        from_version: &str,
+        to_version: &str,
+        preserved_data: PreservedData,
+    ) -> Result<(serde_json::Value, PreservedData), RoundTripFailureReason> {
+        let parsed = self.parse(from_version, value)?;
+
+        self.extension
+            .migrator()
+            .perform_preserving_migration(
+                self.extension,
+                parsed,
+                from_version,
+                to_version,
+                preserved_data,
+            )
+            .map(|result| (result.value, result.preserved_data))
+            .map_err(|e| RoundTripFailureReason::MigrationFailed(e.to_string()))
+    }
+
+    /// Parses a JSON value as the model of the given version.
+    fn parse(
+        &self,
+        version: &str,
+        value: serde_json::Value,
+    ) -> Result<Box<dyn std::any::Any>, RoundTripFailureReason> {
+        self.extension
+            .model(version)
+            .ok_or_else(|| {
+                RoundTripFailureReason::MigrationFailed(format!("no model for version '{version}'"))
+            })?
+            .as_model()
+            .parse_erased(value)
+            .map_err(|e| RoundTripFailureReason::MigrationFailed(e.to_string()))
+    }
+
+    /// Compares an original value to its round-tripped counterpart, ignoring lossy fields.
+    fn compare(
+        &self,
+        version: &str,
+        original: &serde_json::Value,
+        round_tripped: serde_json::Value,
+    ) -> Result<(), RoundTripFailureReason> {
+        let mut expected = original.clone();
+        let mut actual = round_tripped;
+
+        self.lossy_fields
+            .get(version)
+            .into_iter()
+            .flatten()
+            .for_each(|pointer| {
+                remove_pointer(&mut expected, pointer);
+                remove_pointer(&mut actual, pointer);
+            });
+
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(RoundTripFailureReason::ValueChanged { expected, actual })
+        }
+    }
+}
+
+/// Removes the value at the given JSON pointer, if it exists.
+fn remove_pointer(value: &mut serde_json::Value, pointer: &str) {
+    let (parent, key) = match pointer.rsplit_once('/') {
+        Some(split) => split,
+        None => return,
+    };
+    let key = key.replace("~1", "/").replace("~0", "~");
+
+    match value.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => {
+            map.remove(&key);
+        }
+        Some(serde_json::Value::Array(list)) => {
+            if let Ok(index) = key.parse::<usize>() {
+                if index < list.len() {
+                    list.remove(index);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A failed migration round trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripFailure {
+    /// The version of the sample value.
+    pub sample_version: String,
+    /// The version that the sample value was migrated through.
+    pub via_version: String,
+    /// The kind of migration used for the round trip.
+    pub kind: RoundTripKind,
+    /// Why the round trip failed.
+    pub reason: RoundTripFailureReason,
+}
+
+impl Display for RoundTripFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} round trip of '{}' sample via '{}': {}",
+            self.kind, self.sample_version, self.via_version, self.reason
+        )
+    }
+}
+
+/// The kind of migration used for a round trip.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoundTripKind {
+    /// A migration to a target version.
+    Target,
+    /// A flood migration to all versions.
+    Flood,
+}
+
+impl Display for RoundTripKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Target => "target",
+            Self::Flood => "flood",
+        })
+    }
+}
+
+/// The reason that a migration round trip failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundTripFailureReason {
+    /// A migration returned an error.
+    MigrationFailed(String),
+    /// A flood migration result disagreed with a target migration to the same version.
+    FloodMismatch {
+        /// The value produced by the flood migration.
+        flood: serde_json::Value,
+        /// The value produced by the target migration.
+        target: serde_json::Value,
+    },
+    /// The round-tripped value differs from the original.
+    ValueChanged {
+        /// The original value, excluding lossy fields.
+        expected: serde_json::Value,
+        /// The round-tripped value, excluding lossy fields.
+        actual: serde_json::Value,
+    },
+}
+
+impl Display for RoundTripFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MigrationFailed(message) => write!(f, "migration failed: {message}"),
+            Self::FloodMismatch { flood, target } => write!(
+                f,
+                "flood migration produced {flood}, but target migration produced {target}"
+            ),
+            Self::ValueChanged { expected, actual } => {
+                write!(f, "expected {expected}, but got {actual}")
+            }
+        }
+    }
+}
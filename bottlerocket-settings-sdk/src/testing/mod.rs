@@ -0,0 +1,15 @@
+//! Provides utilities for testing settings extensions built with the SDK.
+//!
+//! These utilities are intended to be used from an extension's own test suite, and are only
+//! available when the `testing` feature is enabled:
+//!
+//! * [`ExtensionHarness`] runs proto1 commands against an extension through its CLI interface.
+//! * [`Fixtures`] loads JSON settings values for each version of an extension.
+//! * [`MigrationRoundTrip`] checks that migrations round-trip values between versions.
+pub mod fixtures;
+pub mod harness;
+pub mod migration;
+
+pub use fixtures::Fixtures;
+pub use harness::ExtensionHarness;
+pub use migration::{MigrationRoundTrip, RoundTripFailure};
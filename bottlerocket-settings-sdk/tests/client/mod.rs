@@ -0,0 +1,197 @@
+use argh::FromArgs;
+use bottlerocket_settings_sdk::cli::proto1::{
+    DiffCommand, FloodMigrateCommand, GenerateCommand, ListHelpersCommand, MigrateCommand,
+    Proto1Command, Protocol1, RenderCommand, SetCommand, TemplateHelperCommand, UnsetCommand,
+    ValidateCommand,
+};
+use bottlerocket_settings_sdk::client::{ExtensionClient, ExtensionClientError};
+use bottlerocket_settings_sdk::{GenerateResult, MigrationResult, UnsetResult};
+use maplit::btreemap;
+use serde_json::json;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// A fake settings extension which gives canned responses to proto1 commands.
+const FAKE_EXTENSION: &str = r#"#!/bin/sh
+[ "$1" = "proto1" ] || exit 2
+case "$2" in
+    set) ;;
+    unset) echo '{"Replace": "default"}' ;;
+    diff) echo '{"added": [], "removed": [], "changed": ["/motd"]}' ;;
+    generate) echo '{"Complete": "hello"}' ;;
+    flood-migrate) echo '[{"version": "v1", "value": "a b"}, {"version": "v2", "value": ["a", "b"]}]' ;;
+    helper) echo '"Hello!"' ;;
+    helpers) echo '{"exclaim": null}' ;;
+    validate) echo 'Validate operation failed: bad value'; exit 1 ;;
+    *) echo 'not json' ;;
+esac
+"#;
+
+/// Writes the fake extension to a unique path so that tests may run concurrently.
+fn fake_extension(name: &str) -> ExtensionClient {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("fake-extension-{name}"));
+    std::fs::write(&path, FAKE_EXTENSION).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    ExtensionClient::new(path)
+}
+
+#[test]
+fn test_command_args_round_trip() {
+    // Given any proto1 command,
+    // When it is converted to CLI arguments,
+    // Then those arguments parse back into the same command.
+    let commands = vec![
+        Proto1Command::Set(SetCommand {
+            setting_version: "v1".to_string(),
+            value: Some(json!({"motd": "hi"})),
+            merge_patch: None,
+            json_patch: None,
+            current_value: Some(json!({"motd": "hello"})),
+            target_version: None,
+        }),
+        Proto1Command::Set(SetCommand {
+            setting_version: "v1".to_string(),
+            value: None,
+            merge_patch: None,
+            json_patch: Some(json!([{"op": "replace", "path": "/motd", "value": "hi"}])),
+            current_value: Some(json!({"motd": "hello"})),
+            target_version: Some("v2".to_string()),
+        }),
+        Proto1Command::Unset(UnsetCommand {
+            setting_version: "v1".to_string(),
+            current_value: Some(json!({"motd": "hello"})),
+        }),
+        Proto1Command::Diff(DiffCommand {
+            setting_version: "v1".to_string(),
+            value: json!({"motd": "hi"}),
+            current_value: None,
+        }),
+        Proto1Command::Generate(GenerateCommand {
+            setting_version: "v1".to_string(),
+            existing_partial: None,
+            required_settings: Some(json!({"other": 1})),
+        }),
+        Proto1Command::Validate(ValidateCommand {
+            setting_version: "v2".to_string(),
+            value: json!([1, 2]),
+            required_settings: None,
+            target_version: None,
+        }),
+        Proto1Command::Validate(ValidateCommand {
+            setting_version: "v1".to_string(),
+            value: json!("a b"),
+            required_settings: None,
+            target_version: Some("v2".to_string()),
+        }),
+        Proto1Command::Migrate(MigrateCommand {
+            value: json!("a b"),
+            from_version: "v1".to_string(),
+            target_version: "v2".to_string(),
+            preserve: true,
+            preserved_data: Some(json!({"v2": 1})),
+        }),
+        Proto1Command::FloodMigrate(FloodMigrateCommand {
+            value: json!(null),
+            from_version: "v1".to_string(),
+        }),
+        Proto1Command::Helper(TemplateHelperCommand {
+            setting_version: "v1".to_string(),
+            helper_name: "exclaim".to_string(),
+            arg: vec![json!("one"), json!(2)],
+            hash: vec!["sep=\",\"".parse().unwrap(), "count=3".parse().unwrap()],
+            value: Some(json!("hi")),
+            required_settings: None,
+        }),
+        Proto1Command::Helpers(ListHelpersCommand {
+            setting_version: "v2".to_string(),
+        }),
+        Proto1Command::Render(RenderCommand {
+            setting_version: "v1".to_string(),
+            template: "{{settings.motd}}".to_string(),
+            value: json!("hi"),
+            required_settings: Some(json!({"other": 1})),
+        }),
+    ];
+
+    commands.into_iter().for_each(|command| {
+        assert!(Proto1Command::NAMES.contains(&command.name()));
+
+        let args = command.to_args();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let parsed = Protocol1::from_args(&["proto1"], &args).unwrap();
+
+        assert_eq!(format!("{:?}", parsed.command), format!("{:?}", command));
+    });
+}
+
+#[test]
+fn test_client_parses_results() {
+    let client = fake_extension("parses-results");
+
+    client.set("v1", json!("hello"), None).unwrap();
+    client
+        .set_merge_patch("v1", json!({"motd": "hi"}), Some(json!({"motd": "hello"})))
+        .unwrap();
+    assert_eq!(
+        client.unset::<String>("v1", Some(json!("hello"))).unwrap(),
+        UnsetResult::Replace("default".to_string())
+    );
+    assert_eq!(
+        client
+            .diff("v1", json!({"motd": "hi"}), Some(json!({"motd": "hello"})))
+            .unwrap()
+            .changed,
+        vec!["/motd"]
+    );
+    assert_eq!(
+        client
+            .generate::<serde_json::Value, String>("v1", None, None)
+            .unwrap(),
+        GenerateResult::Complete("hello".to_string())
+    );
+    assert_eq!(
+        client.flood_migrate(json!("a b"), "v1").unwrap(),
+        vec![
+            MigrationResult {
+                version: "v1".to_string(),
+                value: json!("a b"),
+            },
+            MigrationResult {
+                version: "v2".to_string(),
+                value: json!(["a", "b"]),
+            },
+        ]
+    );
+    assert_eq!(
+        client
+            .template_helper("v1", "exclaim", vec![json!("Hello")])
+            .unwrap(),
+        json!("Hello!")
+    );
+    assert_eq!(
+        client.list_helpers("v1").unwrap(),
+        btreemap! { "exclaim".to_string() => None }
+    );
+}
+
+#[test]
+fn test_client_errors() {
+    let client = fake_extension("errors");
+
+    assert!(matches!(
+        client.validate("v1", json!("bad"), None),
+        Err(ExtensionClientError::CommandFailed { message, .. })
+            if message == "Validate operation failed: bad value"
+    ));
+    let err = client.migrate(json!("a b"), "v1", "v2").unwrap_err();
+    assert!(matches!(
+        err,
+        ExtensionClientError::ParseOutput { output_len: 8, .. }
+    ));
+    assert!(!err.to_string().contains("not json"), "{}", err);
+    assert!(matches!(
+        ExtensionClient::new("/no/such/extension").set("v1", json!(1), None),
+        Err(ExtensionClientError::SpawnExtension { .. })
+    ));
+}
@@ -0,0 +1,140 @@
+use bottlerocket_settings_sdk::migrate::null::NullMigrator;
+use bottlerocket_settings_sdk::model::TypeErasedModel;
+use bottlerocket_settings_sdk::testing::ExtensionHarness;
+use bottlerocket_settings_sdk::{
+    assert_extension_error, BottlerocketSetting, GenerateResult, NullMigratorExtensionBuilder,
+    SettingsDiff, SettingsExtension, SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A setting describing a cluster, whose name cannot change once the cluster has been created.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Cluster {
+    name: String,
+    #[serde(default)]
+    endpoints: Vec<String>,
+}
+
+impl SettingsModel for Cluster {
+    type PartialKind = Self;
+    type ErrorKind = anyhow::Error;
+
+    fn get_version() -> &'static str {
+        "v1"
+    }
+
+    fn set(_: Option<Self>, _: Self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn set_with_diff(
+        current_value: Option<Self>,
+        _target: Self,
+        diff: &SettingsDiff,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            current_value.is_none() || !diff.touches("/name"),
+            "cluster name cannot be changed"
+        );
+        Ok(())
+    }
+
+    fn generate(
+        existing_partial: Option<Self::PartialKind>,
+        _: Option<serde_json::Value>,
+    ) -> anyhow::Result<GenerateResult<Self, Self>> {
+        Ok(GenerateResult::Complete(
+            existing_partial.unwrap_or_default(),
+        ))
+    }
+
+    fn validate(_: Self, _: Option<serde_json::Value>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+type ClusterExtension = SettingsExtension<NullMigrator, Box<dyn TypeErasedModel>>;
+
+fn cluster_extension() -> ClusterExtension {
+    NullMigratorExtensionBuilder::with_name("cluster")
+        .with_models(vec![BottlerocketSetting::<Cluster>::model()])
+        .build()
+        .expect("Failed to build cluster settings extension")
+}
+
+fn cluster_harness() -> ExtensionHarness<fn() -> ClusterExtension> {
+    ExtensionHarness::new(cluster_extension)
+}
+
+#[test]
+fn test_diff_command() {
+    // When a diff is requested between two values,
+    // Then the paths which differ are reported, comparing the values as parsed by the model.
+    assert_eq!(
+        cluster_harness()
+            .diff(
+                "v1",
+                json!({"name": "prod", "endpoints": ["a", "c", "d"]}),
+                Some(json!({"name": "prod", "endpoints": ["a", "b"]})),
+            )
+            .unwrap(),
+        SettingsDiff {
+            added: vec!["/endpoints/2".to_string()],
+            removed: vec![],
+            changed: vec!["/endpoints/1".to_string()],
+        }
+    );
+
+    // Defaults filled in while parsing are not differences.
+    assert!(cluster_harness()
+        .diff(
+            "v1",
+            json!({"name": "prod"}),
+            Some(json!({"name": "prod", "endpoints": []})),
+        )
+        .unwrap()
+        .is_empty());
+
+    // When there is no current value,
+    // Then the whole value is added.
+    assert_eq!(
+        cluster_harness()
+            .diff("v1", json!({"name": "prod"}), None)
+            .unwrap()
+            .added,
+        vec![""]
+    );
+
+    // When a value is not valid for the model,
+    // Then the diff fails.
+    assert_extension_error!(
+        cluster_harness().diff("v1", json!({"endpoints": []}), None),
+        Diff
+    );
+}
+
+#[test]
+fn test_set_with_diff() {
+    let current = json!({"name": "prod", "endpoints": ["a"]});
+
+    // When a set only changes fields which may change,
+    // Then the set succeeds.
+    assert!(cluster_harness()
+        .set_with_current(
+            "v1",
+            json!({"name": "prod", "endpoints": ["b"]}),
+            current.clone()
+        )
+        .is_ok());
+    assert!(cluster_harness().set("v1", json!({"name": "dev"})).is_ok());
+
+    // When a set changes a field which the model fixes in place,
+    // Then the set is rejected.
+    let result = cluster_harness().set_with_current("v1", json!({"name": "dev"}), current);
+    assert_extension_error!(&result, Set);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("cluster name cannot be changed"));
+}
@@ -0,0 +1,177 @@
+use bottlerocket_settings_sdk::extension::SettingsExtensionError;
+use bottlerocket_settings_sdk::migrate::null::{NullMigrator, NullMigratorError};
+use bottlerocket_settings_sdk::model::{BottlerocketSettingError, TypeErasedModel};
+use bottlerocket_settings_sdk::testing::ExtensionHarness;
+use bottlerocket_settings_sdk::{
+    BottlerocketSetting, GenerateResult, NullMigratorExtensionBuilder, SettingsExtension,
+    SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+
+/// A setting describing a node, whose identity is fixed and whose storage choices are made once.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Node {
+    cluster_id: String,
+    #[serde(default)]
+    encryption: Option<String>,
+    #[serde(default)]
+    disks: Vec<Disk>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Disk {
+    device: String,
+    #[serde(default)]
+    filesystem: Option<String>,
+}
+
+impl SettingsModel for Node {
+    type PartialKind = Self;
+    type ErrorKind = Infallible;
+
+    fn get_version() -> &'static str {
+        "v1"
+    }
+
+    fn immutable_fields() -> Vec<&'static str> {
+        vec!["/cluster_id"]
+    }
+
+    fn write_once_fields() -> Vec<&'static str> {
+        vec!["/encryption", "/disks/*/filesystem"]
+    }
+
+    fn set(_: Option<Self>, _: Self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn generate(
+        existing_partial: Option<Self::PartialKind>,
+        _: Option<Value>,
+    ) -> Result<GenerateResult<Self, Self>, Infallible> {
+        Ok(GenerateResult::Complete(
+            existing_partial.unwrap_or_default(),
+        ))
+    }
+
+    fn validate(_: Self, _: Option<Value>) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+type NodeExtension = SettingsExtension<NullMigrator, Box<dyn TypeErasedModel>>;
+
+fn node_extension() -> NodeExtension {
+    NullMigratorExtensionBuilder::with_name("node")
+        .with_models(vec![BottlerocketSetting::<Node>::model()])
+        .build()
+        .expect("Failed to build node settings extension")
+}
+
+/// Sets the node setting to `value`, given its `current` value.
+fn set_node(current: Value, value: Value) -> Result<(), SettingsExtensionError<NullMigratorError>> {
+    ExtensionHarness::new(node_extension).set_with_current("v1", value, current)
+}
+
+#[test]
+fn test_immutable_fields() {
+    let current = json!({"cluster_id": "abc", "labels": ["a"]});
+
+    // When there is no current value,
+    // Then immutable fields can be given any value.
+    assert!(ExtensionHarness::new(node_extension)
+        .set("v1", json!({"cluster_id": "xyz"}))
+        .is_ok());
+
+    // When other fields change,
+    // Then the set succeeds.
+    assert!(set_node(
+        current.clone(),
+        json!({"cluster_id": "abc", "labels": ["b"]})
+    )
+    .is_ok());
+
+    // When an immutable field changes,
+    // Then the set is rejected with an error naming the field.
+    let result = set_node(current, json!({"cluster_id": "xyz", "labels": ["a"]}));
+    assert!(matches!(
+        &result,
+        Err(SettingsExtensionError::Set {
+            source: BottlerocketSettingError::ImmutableField { field, .. }
+        }) if field == "/cluster_id"
+    ));
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Cannot change immutable field '/cluster_id' of setting version 'v1'"));
+}
+
+#[test]
+fn test_write_once_fields() {
+    // When a write-once field has no value,
+    // Then it can be given one.
+    assert!(set_node(
+        json!({"cluster_id": "abc"}),
+        json!({"cluster_id": "abc", "encryption": "aes"}),
+    )
+    .is_ok());
+
+    // When a write-once field has a value,
+    // Then changing or removing it is rejected with an error naming the field.
+    let current = json!({"cluster_id": "abc", "encryption": "aes"});
+    for value in [
+        json!({"cluster_id": "abc", "encryption": "none"}),
+        json!({"cluster_id": "abc"}),
+    ] {
+        assert!(matches!(
+            set_node(current.clone(), value),
+            Err(SettingsExtensionError::Set {
+                source: BottlerocketSettingError::WriteOnceField { field, .. }
+            }) if field == "/encryption"
+        ));
+    }
+}
+
+#[test]
+fn test_write_once_fields_with_wildcards() {
+    let current = json!({
+        "cluster_id": "abc",
+        "disks": [{"device": "xvdb", "filesystem": "ext4"}, {"device": "xvdc"}],
+    });
+
+    // When matching fields without values are filled in, or new members are added,
+    // Then the set succeeds.
+    assert!(set_node(
+        current.clone(),
+        json!({
+            "cluster_id": "abc",
+            "disks": [
+                {"device": "xvdb", "filesystem": "ext4"},
+                {"device": "xvdc", "filesystem": "xfs"},
+                {"device": "xvdd", "filesystem": "xfs"},
+            ],
+        }),
+    )
+    .is_ok());
+
+    // When a matching field with a value changes, or is removed along with its parent,
+    // Then the set is rejected with an error naming the field.
+    for value in [
+        json!({
+            "cluster_id": "abc",
+            "disks": [{"device": "xvdb", "filesystem": "xfs"}, {"device": "xvdc"}],
+        }),
+        json!({"cluster_id": "abc", "disks": []}),
+    ] {
+        assert!(matches!(
+            set_node(current.clone(), value),
+            Err(SettingsExtensionError::Set {
+                source: BottlerocketSettingError::WriteOnceField { field, .. }
+            }) if field == "/disks/0/filesystem"
+        ));
+    }
+}
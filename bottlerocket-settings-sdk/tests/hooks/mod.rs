@@ -0,0 +1,99 @@
+use crate::motd::{MotdV1, MotdV2};
+use bottlerocket_settings_sdk::cli::proto1::Proto1Command;
+use bottlerocket_settings_sdk::extension::{CommandHook, HookError};
+use bottlerocket_settings_sdk::testing::ExtensionHarness;
+use bottlerocket_settings_sdk::{
+    assert_extension_error, BottlerocketSetting, LinearMigratorExtensionBuilder,
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Records each command run by an extension, and whether it succeeded.
+#[derive(Default, Clone)]
+struct AuditLog(Arc<Mutex<Vec<String>>>);
+
+impl AuditLog {
+    fn entries(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl CommandHook for AuditLog {
+    fn before(&self, command: &Proto1Command) -> Result<(), HookError> {
+        self.0.lock().unwrap().push(format!(
+            "before {} {}",
+            command.name(),
+            command.setting_version()
+        ));
+        Ok(())
+    }
+
+    fn after(&self, command: &Proto1Command, result: Result<&str, &dyn std::error::Error>) {
+        self.0.lock().unwrap().push(format!(
+            "after {} {}",
+            command.name(),
+            if result.is_ok() { "ok" } else { "failed" }
+        ));
+    }
+}
+
+/// Rejects any attempt to set the given setting version.
+struct ReadOnlyVersion(&'static str);
+
+impl CommandHook for ReadOnlyVersion {
+    fn before(&self, command: &Proto1Command) -> Result<(), HookError> {
+        match command {
+            Proto1Command::Set(_) if command.setting_version() == self.0 => {
+                Err(format!("version '{}' is read-only", self.0).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_command_hooks() {
+    let audit_log = AuditLog::default();
+    let hook = audit_log.clone();
+    let harness = ExtensionHarness::new(move || {
+        LinearMigratorExtensionBuilder::with_name("motd")
+            .with_models(vec![
+                BottlerocketSetting::<MotdV1>::model(),
+                BottlerocketSetting::<MotdV2>::model(),
+            ])
+            .with_hook(hook.clone())
+            .with_hook(ReadOnlyVersion("v1"))
+            .build()
+            .unwrap()
+    });
+
+    // When commands are run,
+    // Then hooks are called before and after each one.
+    harness.validate("v2", json!(["hi"]), None).unwrap();
+    harness.migrate(json!("hi there"), "v1", "v2").unwrap();
+    assert_eq!(
+        audit_log.entries(),
+        vec![
+            "before validate v2",
+            "after validate ok",
+            "before migrate v1",
+            "after migrate ok",
+        ]
+    );
+
+    // When a hook rejects a command,
+    // Then the command is not run, and the rejection is reported to each hook.
+    let result = harness.set("v1", json!("hi"));
+    assert_extension_error!(&result, Hook);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "set operation rejected by hook: version 'v1' is read-only"
+    );
+    assert_eq!(
+        audit_log.entries()[4..],
+        ["before set v1", "after set failed"]
+    );
+
+    harness.set("v2", json!(["hi"])).unwrap();
+    assert_eq!(audit_log.entries().len(), 8);
+}
@@ -0,0 +1,202 @@
+use crate::motd::MotdV1;
+use argh::FromArgs;
+use bottlerocket_settings_sdk::cli::{Cli, LogFormat};
+use bottlerocket_settings_sdk::logging::{LogConfig, LOG_ENV_VAR, LOG_FORMAT_ENV_VAR};
+use bottlerocket_settings_sdk::{BottlerocketSetting, LinearMigratorExtensionBuilder};
+use std::process::{Command, ExitCode};
+use tracing::level_filters::LevelFilter;
+
+/// The environment variable containing the CLI arguments that `serve_logging_extension` should run.
+const LOGGING_EXTENSION_ARGS: &str = "LOGGING_EXTENSION_ARGS";
+
+/// Parses the extension CLI with the given logging flags preceding a `validate` command.
+fn parse_cli(logging_flags: &[&str]) -> Result<Cli, String> {
+    let command = [
+        "proto1",
+        "validate",
+        "--setting-version",
+        "v1",
+        "--value",
+        "\"hi\"",
+    ];
+    let args: Vec<&str> = logging_flags.iter().chain(&command).copied().collect();
+    Cli::from_args(&["motd"], &args).map_err(|e| e.output)
+}
+
+#[test]
+fn test_parse_logging_flags() {
+    // When logging flags are given before the protocol,
+    // then they are parsed alongside the command.
+    let cli = parse_cli(&["--log-level", "debug", "--log-format", "json"]).unwrap();
+    assert_eq!(cli.log_level, Some(LevelFilter::DEBUG));
+    assert_eq!(cli.log_format, Some(LogFormat::Json));
+
+    let cli = parse_cli(&[]).unwrap();
+    assert_eq!(cli.log_level, None);
+    assert_eq!(cli.log_format, None);
+
+    // When an unknown format or level is given,
+    // then parsing fails.
+    assert!(parse_cli(&["--log-format", "xml"]).is_err());
+    assert!(parse_cli(&["--log-level", "loud"]).is_err());
+}
+
+#[test]
+fn test_log_config_precedence() {
+    let config = LogConfig::default().with_filter("info,motd=trace").unwrap();
+    assert_eq!(config.filter(), "info,motd=trace");
+    assert_eq!(config.format(), LogFormat::Text);
+
+    // When logging flags are given,
+    // then they override the existing configuration.
+    let overridden = config
+        .clone()
+        .with_cli_args(&parse_cli(&["--log-format", "json"]).unwrap());
+    assert_eq!(overridden.filter(), "info,motd=trace");
+    assert_eq!(overridden.format(), LogFormat::Json);
+
+    let overridden = config.with_cli_args(&parse_cli(&["--log-level", "error"]).unwrap());
+    assert_eq!(overridden.filter(), "error");
+    assert_eq!(overridden.format(), LogFormat::Text);
+
+    // When invalid filter directives are given,
+    // then an error is returned.
+    assert!(LogConfig::default().with_filter("motd=[").is_err());
+}
+
+#[test]
+fn test_run_with_logging_flags() {
+    // When an extension is run with logging flags,
+    // then the command runs as usual.
+    let extension = LinearMigratorExtensionBuilder::with_name("motd")
+        .with_models(vec![BottlerocketSetting::<MotdV1>::model()])
+        .build()
+        .unwrap();
+
+    let result = extension.try_run_with_args([
+        "motd",
+        "--log-level",
+        "trace",
+        "proto1",
+        "validate",
+        "--setting-version",
+        "v1",
+        "--value",
+        "\"hello\"",
+    ]);
+    assert_eq!(result.unwrap(), "");
+}
+
+/// Serves the motd extension when this test binary is run as a subprocess by the tests below.
+///
+/// Loggers are only installed for extensions which own their process, so the tests run the
+/// extension from a copy of the test process.
+#[test]
+fn serve_logging_extension() -> ExitCode {
+    match std::env::var(LOGGING_EXTENSION_ARGS) {
+        Ok(args) => {
+            let args: Vec<String> = serde_json::from_str(&args).unwrap();
+            LinearMigratorExtensionBuilder::with_name("motd")
+                .with_models(vec![BottlerocketSetting::<MotdV1>::model()])
+                .build()
+                .unwrap()
+                .run_with_args(args)
+        }
+        Err(_) => ExitCode::SUCCESS,
+    }
+}
+
+/// Runs the motd extension's `generate` command in a subprocess with the given logging flags and
+/// environment variables, returning its stdout and stderr.
+fn run_logging_extension(logging_flags: &[&str], env: &[(&str, &str)]) -> (String, String) {
+    let command = ["proto1", "generate", "--setting-version", "v1"];
+    let args: Vec<&str> = std::iter::once("motd")
+        .chain(logging_flags.iter().copied())
+        .chain(command)
+        .collect();
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "logging::serve_logging_extension", "--nocapture"])
+        .env(
+            LOGGING_EXTENSION_ARGS,
+            serde_json::to_string(&args).unwrap(),
+        )
+        .env_remove(LOG_ENV_VAR)
+        .env_remove(LOG_FORMAT_ENV_VAR)
+        .envs(env.iter().copied())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Returns the log lines written by the extension as JSON objects.
+fn json_logs(stderr: &str) -> Vec<serde_json::Value> {
+    stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|line| line.get("level").is_some())
+        .collect()
+}
+
+#[test]
+fn test_logs_written_to_stderr() {
+    // When an extension is run with logging flags,
+    // Then logs are written to stderr in the requested format, and stdout only contains the
+    // command's output.
+    let (stdout, stderr) =
+        run_logging_extension(&["--log-level", "debug", "--log-format", "json"], &[]);
+    let logs = json_logs(&stderr);
+    assert!(
+        logs.iter()
+            .any(|log| log["fields"]["message"] == "Starting settings extensions"),
+        "{}",
+        stderr
+    );
+    assert!(logs.iter().any(|log| log["level"] == "DEBUG"), "{}", stderr);
+    assert!(stdout.contains(r#""Complete": "#), "{}", stdout);
+    assert!(
+        !stdout.contains("Starting settings extensions"),
+        "{}",
+        stdout
+    );
+    assert!(json_logs(&stdout).is_empty(), "{}", stdout);
+
+    // When the logging environment variables are set,
+    // Then they configure the logs.
+    let (stdout, stderr) =
+        run_logging_extension(&[], &[(LOG_ENV_VAR, "info"), (LOG_FORMAT_ENV_VAR, "json")]);
+    let logs = json_logs(&stderr);
+    assert!(!logs.is_empty(), "{}", stderr);
+    assert!(logs.iter().all(|log| log["level"] != "DEBUG"), "{}", stderr);
+    assert!(
+        !stdout.contains("Starting settings extensions"),
+        "{}",
+        stdout
+    );
+
+    // When both are given,
+    // Then the flags take precedence.
+    let (_, stderr) = run_logging_extension(
+        &["--log-format", "text"],
+        &[(LOG_ENV_VAR, "info"), (LOG_FORMAT_ENV_VAR, "json")],
+    );
+    assert!(
+        stderr.contains("Starting settings extensions"),
+        "{}",
+        stderr
+    );
+    assert!(json_logs(&stderr).is_empty(), "{}", stderr);
+
+    // When no logging is configured,
+    // Then only warnings and errors are logged.
+    let (_, stderr) = run_logging_extension(&[], &[]);
+    assert!(
+        !stderr.contains("Starting settings extensions"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,237 @@
+use super::motd::{MotdV1, MotdV2};
+use anyhow::Result;
+use bottlerocket_settings_sdk::migrate::LinearMigratorModel;
+use bottlerocket_settings_sdk::testing::MigrationRoundTrip;
+use bottlerocket_settings_sdk::{
+    BottlerocketSetting, GenerateResult, LinearMigrator, LinearMigratorExtensionBuilder,
+    LinearlyMigrateable, NoMigration, SettingsExtension, SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+
+fn motd_settings_extension() -> SettingsExtension<LinearMigrator, LinearMigratorModel> {
+    LinearMigratorExtensionBuilder::with_name("motd")
+        .with_models(vec![
+            BottlerocketSetting::<MotdV1>::model(),
+            BottlerocketSetting::<MotdV2>::model(),
+        ])
+        .build()
+        .expect("Failed to build motd settings extension")
+}
+
+#[test]
+fn test_motd_round_trips() {
+    // Given normalized motd values in each version,
+    // When they are migrated through all other versions,
+    // Then the original values are returned.
+    let extension = motd_settings_extension();
+
+    MigrationRoundTrip::new(&extension)
+        .with_samples(vec![MotdV1(None), MotdV1(Some("hello world".to_string()))])
+        .with_sample(MotdV2(vec!["hello".to_string(), "world".to_string()]))
+        .with_generated_samples()
+        .assert_round_trips();
+}
+
+#[test]
+fn test_motd_lossy_round_trip_detected() {
+    // Given a motd value whose whitespace is lost when migrating,
+    // When it is migrated through all other versions,
+    // Then the round trip failure is reported for both target and flood migrations.
+    let extension = motd_settings_extension();
+
+    let failures = MigrationRoundTrip::new(&extension)
+        .with_sample(MotdV1(Some("too   many spaces".to_string())))
+        .check();
+
+    assert_eq!(failures.len(), 2);
+    assert!(failures
+        .iter()
+        .all(|failure| failure.sample_version == "v1" && failure.via_version == "v2"));
+}
+
+// A pair of models where migrating backward loses the `nickname` field.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProfileV1 {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProfileV2 {
+    name: String,
+    nickname: Option<String>,
+}
+
+macro_rules! define_model {
+    ($name:ident, $version:expr) => {
+        impl SettingsModel for $name {
+            type PartialKind = Self;
+            type ErrorKind = anyhow::Error;
+
+            fn get_version() -> &'static str {
+                $version
+            }
+
+            fn set(_: Option<Self>, _: Self) -> Result<()> {
+                Ok(())
+            }
+
+            fn generate(
+                _: Option<Self::PartialKind>,
+                _: Option<serde_json::Value>,
+            ) -> Result<GenerateResult<Self::PartialKind, Self>> {
+                Ok(GenerateResult::Complete(Self::default()))
+            }
+
+            fn validate(_: Self, _: Option<serde_json::Value>) -> Result<()> {
+                Ok(())
+            }
+        }
+    };
+}
+
+define_model!(ProfileV1, "v1");
+define_model!(ProfileV2, "v2");
+
+impl LinearlyMigrateable for ProfileV1 {
+    type ForwardMigrationTarget = ProfileV2;
+    type BackwardMigrationTarget = NoMigration;
+
+    fn migrate_forward(&self) -> Result<ProfileV2> {
+        Ok(ProfileV2 {
+            name: self.name.clone(),
+            nickname: None,
+        })
+    }
+
+    fn migrate_backward(&self) -> Result<NoMigration> {
+        NoMigration::no_defined_migration()
+    }
+}
+
+impl LinearlyMigrateable for ProfileV2 {
+    type ForwardMigrationTarget = NoMigration;
+    type BackwardMigrationTarget = ProfileV1;
+
+    fn migrate_forward(&self) -> Result<NoMigration> {
+        NoMigration::no_defined_migration()
+    }
+
+    fn migrate_backward(&self) -> Result<ProfileV1> {
+        Ok(ProfileV1 {
+            name: self.name.clone(),
+        })
+    }
+}
+
+#[test]
+fn test_declared_lossy_fields() {
+    // Given a model which loses a field when migrating backward,
+    // When the lost field is declared as lossy,
+    // Then the round trip succeeds only with the declaration.
+    let extension = LinearMigratorExtensionBuilder::with_name("profile")
+        .with_models(vec![
+            BottlerocketSetting::<ProfileV1>::model(),
+            BottlerocketSetting::<ProfileV2>::model(),
+        ])
+        .build()
+        .unwrap();
+    let sample = || ProfileV2 {
+        name: "Robert".to_string(),
+        nickname: Some("Bob".to_string()),
+    };
+
+    assert!(!MigrationRoundTrip::new(&extension)
+        .with_sample(sample())
+        .check()
+        .is_empty());
+
+    MigrationRoundTrip::new(&extension)
+        .with_sample(sample())
+        .with_lossy_field("v2", "/nickname")
+        .assert_round_trips();
+}
+
+// The same pair of models, where migrating backward preserves the `nickname` field instead.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct PreservingProfileV1 {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct PreservingProfileV2 {
+    name: String,
+    nickname: Option<String>,
+}
+
+define_model!(PreservingProfileV1, "v1");
+define_model!(PreservingProfileV2, "v2");
+
+impl LinearlyMigrateable for PreservingProfileV1 {
+    type ForwardMigrationTarget = PreservingProfileV2;
+    type BackwardMigrationTarget = NoMigration;
+
+    fn migrate_forward(&self) -> Result<PreservingProfileV2> {
+        self.migrate_forward_restoring(None)
+    }
+
+    fn migrate_forward_restoring(
+        &self,
+        preserved: Option<serde_json::Value>,
+    ) -> Result<PreservingProfileV2> {
+        Ok(PreservingProfileV2 {
+            name: self.name.clone(),
+            nickname: preserved.map(serde_json::from_value).transpose()?,
+        })
+    }
+
+    fn migrate_backward(&self) -> Result<NoMigration> {
+        NoMigration::no_defined_migration()
+    }
+}
+
+impl LinearlyMigrateable for PreservingProfileV2 {
+    type ForwardMigrationTarget = NoMigration;
+    type BackwardMigrationTarget = PreservingProfileV1;
+
+    fn migrate_forward(&self) -> Result<NoMigration> {
+        NoMigration::no_defined_migration()
+    }
+
+    fn migrate_backward(&self) -> Result<PreservingProfileV1> {
+        self.migrate_backward_preserving().map(|(v1, _)| v1)
+    }
+
+    fn migrate_backward_preserving(
+        &self,
+    ) -> Result<(PreservingProfileV1, Option<serde_json::Value>)> {
+        Ok((
+            PreservingProfileV1 {
+                name: self.name.clone(),
+            },
+            self.nickname
+                .as_ref()
+                .map(|nickname| serde_json::json!(nickname)),
+        ))
+    }
+}
+
+#[test]
+fn test_preserved_fields_round_trip() {
+    // Given a model which preserves a field when migrating backward,
+    // When it is migrated through other versions, both by target and flood migrations,
+    // Then the round trip succeeds without declaring the field lossy.
+    let extension = LinearMigratorExtensionBuilder::with_name("profile")
+        .with_models(vec![
+            BottlerocketSetting::<PreservingProfileV1>::model(),
+            BottlerocketSetting::<PreservingProfileV2>::model(),
+        ])
+        .build()
+        .unwrap();
+
+    MigrationRoundTrip::new(&extension)
+        .with_sample(PreservingProfileV2 {
+            name: "Robert".to_string(),
+            nickname: Some("Bob".to_string()),
+        })
+        .assert_round_trips();
+}
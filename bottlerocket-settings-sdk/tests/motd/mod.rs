@@ -1,8 +1,10 @@
-use super::*;
 use bottlerocket_settings_sdk::migrate::LinearMigratorModel;
+use bottlerocket_settings_sdk::testing::{ExtensionHarness, Fixtures};
 use bottlerocket_settings_sdk::{
-    BottlerocketSetting, LinearMigrator, LinearMigratorExtensionBuilder, SettingsExtension,
+    assert_extension_error, provide_template_helpers, BottlerocketSetting, LinearMigrator,
+    LinearMigratorExtensionBuilder, MigrationResult, SettingsExtension,
 };
+use bottlerocket_template_helper::template_helper;
 use serde_json::json;
 
 // These modules implement two versions of the "motd" settings extension, as well as CLI tests
@@ -20,60 +22,139 @@ fn motd_settings_extension() -> SettingsExtension<LinearMigrator, LinearMigrator
             BottlerocketSetting::<v1::MotdV1>::model(),
             BottlerocketSetting::<v2::MotdV2>::model(),
         ])
+        .with_template_helpers(provide_template_helpers! {
+            "shout" => shout_helper,
+            "question" => question_helper,
+        })
         .build()
         .expect("Failed to build motd settings extension")
 }
 
+// These helpers are available to all motd versions, unless a version provides its own.
+#[template_helper(ident = shout_helper)]
+fn shout(s: &str) -> String {
+    s.to_uppercase()
+}
+
+#[template_helper(ident = question_helper)]
+fn question(s: &str) -> String {
+    format!("{}?", s)
+}
+
+/// Helper to create a CLI test harness for the settings extension.
+fn motd_harness() -> ExtensionHarness<fn() -> SettingsExtension<LinearMigrator, LinearMigratorModel>>
+{
+    ExtensionHarness::new(motd_settings_extension)
+}
+
 #[test]
 fn test_target_migration() {
     // When a target migration is called,
     // then an equivalent value for the target version is produced via migrations.
     assert_eq!(
-        target_migrate_cli(
-            motd_settings_extension(),
-            json!("test target migration!"),
-            "v1",
-            "v2"
-        )
-        .unwrap(),
+        motd_harness()
+            .migrate(json!("test target migration!"), "v1", "v2")
+            .unwrap(),
         json!(["test", "target", "migration!"])
     );
 }
 
+#[test]
+fn test_cross_version_set_and_validate() {
+    // When values are given in an older version than the one in which the setting is stored,
+    // Then they are migrated to the stored version before being checked.
+    motd_harness()
+        .set_with_target_version("v1", "v2", json!("hello there"), Some(json!("hi")))
+        .unwrap();
+    motd_harness()
+        .validate_with_target_version("v1", "v2", json!("hello there"), None)
+        .unwrap();
+
+    // Without migration, the older value is not a valid value of the stored version.
+    assert_extension_error!(motd_harness().set("v2", json!("hello there")), Set);
+
+    // When a value is not valid for the version it is given in,
+    // Then it cannot be migrated.
+    assert_extension_error!(
+        motd_harness().set_with_target_version("v1", "v2", json!(123), None),
+        ModelParse
+    );
+    assert_extension_error!(
+        motd_harness().validate_with_target_version("v1", "v2", json!(["hello"]), None),
+        ModelParse
+    );
+
+    // When the target version is unknown,
+    // Then the command fails.
+    assert_extension_error!(
+        motd_harness().set_with_target_version("v1", "v3", json!("hello"), None),
+        NoSuchModel
+    );
+}
+
 #[test]
 fn test_flood_migration() {
     // When flood is called,
     // equivalent values for all versions are produced via migrations.
-    let expected_flood_results = json!([
-        {
-            "version": "v1",
-            "value": "test flood migration!"
+    let expected_flood_results = vec![
+        MigrationResult {
+            version: "v1".to_string(),
+            value: json!("test flood migration!"),
+        },
+        MigrationResult {
+            version: "v2".to_string(),
+            value: json!(["test", "flood", "migration!"]),
         },
-        {
-            "version": "v2",
-            "value": ["test", "flood", "migration!"]
-        }
-    ]);
+    ];
     assert_eq!(
-        flood_migrate_cli(
-            motd_settings_extension(),
-            json!("test flood migration!"),
-            "v1"
-        )
-        .unwrap(),
+        motd_harness()
+            .flood_migrate(json!("test flood migration!"), "v1")
+            .unwrap(),
         expected_flood_results
     );
     assert_eq!(
-        flood_migrate_cli(
-            motd_settings_extension(),
-            json!(["test", "flood", "migration!"]),
-            "v2"
-        )
-        .unwrap(),
+        motd_harness()
+            .flood_migrate(json!(["test", "flood", "migration!"]), "v2")
+            .unwrap(),
         expected_flood_results
     );
 }
 
+#[test]
+fn test_extension_template_helpers() {
+    // When a helper registered for all versions is called,
+    // Then it is available to every version.
+    for version in ["v1", "v2"] {
+        assert_eq!(
+            motd_harness()
+                .template_helper(version, "shout", vec![json!("hi")])
+                .unwrap(),
+            json!("HI")
+        );
+    }
+
+    // When a version provides a helper with the same name,
+    // Then the version's helper takes precedence.
+    assert_eq!(
+        motd_harness()
+            .template_helper("v1", "question", vec![json!("why")])
+            .unwrap(),
+        json!("why?")
+    );
+    assert_eq!(
+        motd_harness()
+            .template_helper("v2", "question", vec![json!("why"), json!("how")])
+            .unwrap(),
+        json!("why? how??")
+    );
+    assert_eq!(
+        motd_harness()
+            .render("v1", "{{shout settings.motd}}", json!("hello"), None)
+            .unwrap(),
+        "HELLO"
+    );
+}
+
 #[test]
 fn test_migration_types_mutually_exclusive() {
     // When a migration is called with both a target and flood,
@@ -95,3 +176,94 @@ fn test_migration_types_mutually_exclusive() {
 
     assert!(extension.try_run_with_args(args).is_err())
 }
+
+#[test]
+fn test_preserving_migration() {
+    // When a migration is called with `--preserve`,
+    // then the migrated value is returned alongside any preserved data.
+    let extension = motd_settings_extension();
+    let args = vec![
+        "extension",
+        "proto1",
+        "migrate",
+        "--value",
+        r#"["test", "preserving", "migration!"]"#,
+        "--from-version",
+        "v2",
+        "--target-version",
+        "v1",
+        "--preserve",
+    ];
+
+    let result: serde_json::Value =
+        serde_json::from_str(&extension.try_run_with_args(args).unwrap()).unwrap();
+    assert_eq!(
+        result,
+        json!({
+            "value": "test preserving migration!",
+            "preserved_data": {}
+        })
+    );
+}
+
+#[test]
+fn test_flood_migration_results_deserialize() {
+    // When flood migration output is parsed,
+    // then it deserializes into owned `MigrationResult`s which round-trip through JSON.
+    let output = motd_settings_extension()
+        .try_run_with_args([
+            "extension",
+            "proto1",
+            "flood-migrate",
+            "--value",
+            r#""owned results""#,
+            "--from-version",
+            "v1",
+        ])
+        .unwrap();
+
+    let results: Vec<MigrationResult> = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        results,
+        vec![
+            MigrationResult {
+                version: "v1".to_string(),
+                value: json!("owned results"),
+            },
+            MigrationResult {
+                version: "v2".to_string(),
+                value: json!(["owned", "results"]),
+            },
+        ]
+    );
+    assert_eq!(
+        serde_json::from_value::<Vec<MigrationResult>>(serde_json::to_value(&results).unwrap())
+            .unwrap(),
+        results
+    );
+}
+
+#[test]
+fn test_fixture_migrations() {
+    // Given equivalent fixtures for each motd version,
+    // When each fixture is migrated to the other version,
+    // Then the other version's fixture is produced.
+    let fixtures = Fixtures::new("tests/fixtures/motd");
+
+    assert_eq!(
+        motd_harness()
+            .migrate(fixtures.value("v1", "greeting"), "v1", "v2")
+            .unwrap(),
+        fixtures.value("v2", "greeting")
+    );
+    assert_eq!(
+        motd_harness()
+            .migrate(fixtures.value("v2", "greeting"), "v2", "v1")
+            .unwrap(),
+        fixtures.value("v1", "greeting")
+    );
+    assert_eq!(
+        fixtures.model::<MotdV1>("greeting"),
+        MotdV1(Some("Hello, world!".to_string()))
+    );
+}
@@ -1,9 +1,11 @@
 use std::convert::Infallible;
 
 use super::*;
+use bottlerocket_settings_sdk::assert_extension_error;
+use bottlerocket_settings_sdk::helper::{HelperContext, HelperInvocation};
 use bottlerocket_settings_sdk::{
-    provide_template_helpers, GenerateResult, HelperDef, LinearlyMigrateable, NoMigration,
-    SettingsModel,
+    provide_template_helpers, GenerateResult, HelperDef, HelperError, LinearlyMigrateable,
+    NoMigration, SettingsModel, UnsetResult,
 };
 use bottlerocket_template_helper::template_helper;
 use serde::{Deserialize, Serialize};
@@ -52,6 +54,7 @@ impl SettingsModel for MotdV1 {
     fn template_helpers() -> Result<HashMap<String, Box<dyn HelperDef>>> {
         Ok(provide_template_helpers! {
             "exclaim" => exclaim_helper,
+            "motd_or" => motd_or_helper,
         })
     }
 }
@@ -82,15 +85,22 @@ fn exclaim(i: String) -> Result<String> {
     Ok(i + "!")
 }
 
+/// Returns the current motd, or the given default if there is none.
+#[template_helper(ident = motd_or_helper)]
+fn motd_or(#[helper_context] context: HelperContext, default: String) -> Result<String> {
+    Ok(match context.value {
+        Some(serde_json::Value::String(motd)) => motd,
+        _ => default,
+    })
+}
+
 #[test]
 fn test_motdv1_set_success() {
     // When set is called on motdv1 with a string input,
     // Then that input is successfully set.
     vec![json!("Hello!"), json!("")]
         .into_iter()
-        .for_each(
-            |value| assert!(set_cli(motd_settings_extension(), "v1", value.clone()).is_ok(),),
-        );
+        .for_each(|value| assert!(motd_harness().set("v1", value.clone()).is_ok(),));
 }
 
 #[test]
@@ -99,9 +109,27 @@ fn test_motdv1_set_failure() {
     // Then that set operation fails.
     vec![json!(123456789), json!({"motd": "Hello!"})]
         .into_iter()
-        .for_each(
-            |value| assert!(set_cli(motd_settings_extension(), "v1", value.clone()).is_err()),
-        );
+        .for_each(|value| assert!(motd_harness().set("v1", value.clone()).is_err()));
+}
+
+#[test]
+fn test_motdv1_unset() {
+    // When unset is called on motdv1, which doesn't customize removal,
+    // Then the value may be removed.
+    assert_eq!(
+        motd_harness()
+            .unset::<MotdV1>("v1", Some(json!("Hello!")))
+            .unwrap(),
+        UnsetResult::Remove
+    );
+    assert_eq!(
+        motd_harness().unset::<MotdV1>("v1", None).unwrap(),
+        UnsetResult::Remove
+    );
+
+    // When the current value is invalid,
+    // Then the unset operation fails.
+    assert_extension_error!(motd_harness().unset::<MotdV1>("v1", Some(json!(1))), Unset);
 }
 
 #[test]
@@ -109,7 +137,7 @@ fn test_motdv1_generate() {
     // When generate is called on motdv1,
     // an empty settings object is returned.
     assert_eq!(
-        generate_cli(motd_settings_extension(), "v1", None, None).unwrap(),
+        motd_harness().generate("v1", None, None).unwrap(),
         GenerateResult::<MotdV1, MotdV1>::Complete(MotdV1(None))
     );
 }
@@ -118,40 +146,153 @@ fn test_motdv1_generate() {
 fn test_motdv1_validate() {
     // When validate is called on motdv1,
     // it is successful for any value that parses
-    assert!(validate_cli(motd_settings_extension(), "v1", json!("test"), None).is_ok(),);
+    assert!(motd_harness().validate("v1", json!("test"), None).is_ok(),);
 }
 
 #[test]
 fn test_motdv1_failure() {
-    assert!(validate_cli(motd_settings_extension(), "v1", json!([1, 2, 3]), None).is_err(),);
+    assert!(motd_harness()
+        .validate("v1", json!([1, 2, 3]), None)
+        .is_err(),);
 }
 
 #[test]
 fn test_no_such_helper() {
-    assert!(template_helper_cli(motd_settings_extension(), "v1", "no_such_helper", vec![]).is_err())
+    assert_extension_error!(
+        motd_harness().template_helper("v1", "no_such_helper", vec![]),
+        TemplateHelper
+    );
 }
 
 #[test]
 fn test_run_exclaim_helper() {
     assert_eq!(
-        template_helper_cli(
-            motd_settings_extension(),
+        motd_harness()
+            .template_helper("v1", "exclaim", vec![json!("Hello")])
+            .unwrap(),
+        json!("Hello!")
+    );
+}
+
+#[test]
+fn test_helper_too_many_args() {
+    assert!(motd_harness()
+        .template_helper(
             "v1",
             "exclaim",
-            vec![json!("Hello")]
+            vec![json!("too"), json!("many"), json!("arguments")]
         )
-        .unwrap(),
-        json!("Hello!")
+        .is_err());
+}
+
+#[test]
+fn test_render_template() {
+    // When a template is rendered with motdv1's helpers,
+    // Then the setting value and other settings are available to it.
+    assert_eq!(
+        motd_harness()
+            .render(
+                "v1",
+                "{{exclaim settings.motd}} Welcome to {{settings.hostname}}.",
+                json!("Hello"),
+                Some(json!({"hostname": "localhost"})),
+            )
+            .unwrap(),
+        "Hello! Welcome to localhost."
     );
 }
 
 #[test]
-fn test_helper_too_many_args() {
-    assert!(template_helper_cli(
-        motd_settings_extension(),
-        "v1",
-        "exclaim",
-        vec![json!("too"), json!("many"), json!("arguments")]
-    )
-    .is_err());
+fn test_render_template_failures() {
+    // When a template refers to missing settings, or is malformed,
+    // Then rendering fails.
+    vec!["{{settings.hostname}}", "{{exclaim settings.motd"]
+        .into_iter()
+        .for_each(|template| {
+            assert_extension_error!(
+                motd_harness().render("v1", template, json!("Hello"), None),
+                Render
+            )
+        });
+
+    assert_extension_error!(
+        motd_harness().render("v1", "{{settings.motd}}", json!("Hello"), Some(json!([]))),
+        Render
+    );
+
+    // When the required settings are not an object,
+    // Then only their type is reported, since they may contain sensitive values.
+    let err = motd_harness()
+        .render(
+            "v1",
+            "{{settings.motd}}",
+            json!("Hello"),
+            Some(json!(["hunter2"])),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("got a JSON array"), "{}", err);
+    assert!(!err.contains("hunter2"), "{}", err);
+}
+
+#[test]
+fn test_context_aware_helper() {
+    // When a context-aware helper is called with the setting's value,
+    // Then the helper receives it without it being passed as an argument.
+    assert_eq!(
+        motd_harness()
+            .template_helper(
+                "v1",
+                "motd_or",
+                HelperInvocation::new(vec![json!("default")])
+                    .with_context(HelperContext::default().with_value(json!("Hello"))),
+            )
+            .unwrap(),
+        json!("Hello")
+    );
+    assert_eq!(
+        motd_harness()
+            .template_helper("v1", "motd_or", vec![json!("default")])
+            .unwrap(),
+        json!("default")
+    );
+    assert_eq!(
+        motd_harness()
+            .render("v1", "{{motd_or \"default\"}}", json!("Hello"), None)
+            .unwrap(),
+        "Hello"
+    );
+
+    // When a context-aware helper is called without a context,
+    // Then it fails rather than falling back to its default.
+    assert!(matches!(
+        motd_or_helper(vec![json!("default")]),
+        Err(HelperError::MissingContext)
+    ));
+}
+
+#[test]
+fn test_list_helpers() {
+    // When the helpers available to a setting version are listed,
+    // Then helpers generated by `template_helper` are listed with their signatures.
+    let helpers = motd_harness().list_helpers("v1").unwrap();
+
+    assert_eq!(
+        helpers.keys().collect::<Vec<_>>(),
+        vec!["exclaim", "motd_or", "question", "shout"]
+    );
+    let exclaim = helpers["exclaim"].as_ref().unwrap();
+    assert_eq!(exclaim.name, "exclaim");
+    assert_eq!(exclaim.params[0].name, "i");
+    assert!(!exclaim.uses_context);
+
+    let motd_or = helpers["motd_or"].as_ref().unwrap();
+    assert!(motd_or.uses_context);
+    assert_eq!(motd_or.params[0].name, "default");
+    assert_eq!(
+        motd_or.doc.as_deref(),
+        Some("Returns the current motd, or the given default if there is none.")
+    );
+
+    assert_extension_error!(motd_harness().list_helpers("v3"), NoSuchModel);
 }
@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use super::*;
 use anyhow::Result;
+use bottlerocket_settings_sdk::assert_extension_error;
+use bottlerocket_settings_sdk::helper::HelperInvocation;
 use bottlerocket_settings_sdk::{
-    provide_template_helpers, GenerateResult, HelperDef, LinearlyMigrateable, NoMigration,
-    SettingsModel,
+    provide_template_helpers, GenerateResult, HelperDef, HelperError, LinearlyMigrateable,
+    NoMigration, SettingsModel, UnsetResult,
 };
 use bottlerocket_template_helper::template_helper;
 use serde::{Deserialize, Serialize};
@@ -31,6 +33,21 @@ impl SettingsModel for MotdV2 {
         Ok(())
     }
 
+    fn unset(current_value: Option<Self>) -> Result<UnsetResult<Self>> {
+        // A pinned motd can't be removed.
+        anyhow::ensure!(
+            !current_value
+                .unwrap_or_default()
+                .0
+                .iter()
+                .any(|s| s == "pinned"),
+            "Cannot unset a pinned motd"
+        );
+
+        // Otherwise, the motd is reset to its generated default.
+        Ok(UnsetResult::Replace(MotdV2(vec![])))
+    }
+
     fn generate(
         existing_partial: Option<Self::PartialKind>,
         // We do not depend on any settings
@@ -57,6 +74,11 @@ impl SettingsModel for MotdV2 {
         Ok(provide_template_helpers! {
             "exclaim" => exclaim_helper,
             "question" => question_helper,
+            "join" => join_helper,
+            "abbreviate" => Abbreviations::new(),
+            "count" => |args: Vec<serde_json::Value>| -> Result<serde_json::Value, HelperError> {
+                Ok(json!(args.len()))
+            },
         })
     }
 }
@@ -93,6 +115,33 @@ fn question(one: String, two: String) -> Result<String> {
     Ok(format!("{}? {}??", one, two))
 }
 
+#[template_helper(ident = join_helper)]
+fn join(#[variadic] words: Vec<String>, #[keyword] sep: Option<String>) -> Result<String> {
+    Ok(words.join(sep.as_deref().unwrap_or(" ")))
+}
+
+/// A stateful helper which holds a table of abbreviations.
+struct Abbreviations {
+    table: HashMap<String, String>,
+}
+
+#[template_helper]
+impl Abbreviations {
+    fn new() -> Self {
+        Self {
+            table: HashMap::from([
+                ("hello".to_string(), "hi".to_string()),
+                ("goodbye".to_string(), "bye".to_string()),
+            ]),
+        }
+    }
+
+    /// Abbreviates a word, if it has a known abbreviation.
+    fn abbreviate(&self, word: String) -> Result<String> {
+        Ok(self.table.get(&word).cloned().unwrap_or(word))
+    }
+}
+
 #[test]
 fn test_motdv2_set_success() {
     // When set is called on motdv2 with allowed input,
@@ -102,7 +151,7 @@ fn test_motdv2_set_success() {
         json!([]),
     ]
     .into_iter()
-    .for_each(|value| assert!(set_cli(motd_settings_extension(), "v2", value.clone()).is_ok()));
+    .for_each(|value| assert!(motd_harness().set("v2", value.clone()).is_ok()));
 }
 
 #[test]
@@ -116,13 +165,32 @@ fn test_motdv2_set_failure() {
         json!(null),
     ]
     .into_iter()
-    .for_each(|value| assert!(set_cli(motd_settings_extension(), "v2", value).is_err()));
+    .for_each(|value| assert!(motd_harness().set("v2", value).is_err()));
+}
+
+#[test]
+fn test_motdv2_unset() {
+    // When unset is called on motdv2,
+    // Then the motd is reset to its default.
+    assert_eq!(
+        motd_harness()
+            .unset("v2", Some(json!(["hello", "world"])))
+            .unwrap(),
+        UnsetResult::Replace(MotdV2(vec![]))
+    );
+
+    // When unset is called on a pinned motdv2,
+    // Then the removal is rejected.
+    assert_extension_error!(
+        motd_harness().unset::<MotdV2>("v2", Some(json!(["pinned", "hello"]))),
+        Unset
+    );
 }
 
 #[test]
 fn test_motdv2_generate() {
     assert_eq!(
-        generate_cli(motd_settings_extension(), "v2", None, None).unwrap(),
+        motd_harness().generate("v2", None, None).unwrap(),
         GenerateResult::<MotdV2, MotdV2>::Complete(MotdV2(vec![]))
     );
 }
@@ -130,13 +198,9 @@ fn test_motdv2_generate() {
 #[test]
 fn test_run_exclaim_helper() {
     assert_eq!(
-        template_helper_cli(
-            motd_settings_extension(),
-            "v2",
-            "exclaim",
-            vec![json!("Hello")]
-        )
-        .unwrap(),
+        motd_harness()
+            .template_helper("v2", "exclaim", vec![json!("Hello")])
+            .unwrap(),
         json!("Hello!!")
     );
 }
@@ -144,13 +208,171 @@ fn test_run_exclaim_helper() {
 #[test]
 fn test_run_question_helper() {
     assert_eq!(
-        template_helper_cli(
-            motd_settings_extension(),
+        motd_harness()
+            .template_helper("v2", "question", vec![json!("two args"), json!("really")])
+            .unwrap(),
+        json!("two args? really??")
+    );
+}
+
+#[test]
+fn test_run_join_helper_with_keyword() {
+    // When a helper is called with named arguments,
+    // Then they are passed to its keyword parameters.
+    assert_eq!(
+        motd_harness()
+            .template_helper(
+                "v2",
+                "join",
+                HelperInvocation::new(vec![json!("a"), json!("b")])
+                    .with_hash_arg("sep", json!(",")),
+            )
+            .unwrap(),
+        json!("a,b")
+    );
+    assert_eq!(
+        motd_harness()
+            .template_helper("v2", "join", vec![json!("a"), json!("b")])
+            .unwrap(),
+        json!("a b")
+    );
+    assert_extension_error!(
+        motd_harness().template_helper(
             "v2",
-            "question",
-            vec![json!("two args"), json!("really")]
+            "join",
+            HelperInvocation::new(vec![]).with_hash_arg("separator", json!(",")),
+        ),
+        TemplateHelper
+    );
+}
+
+#[test]
+fn test_render_join_helper_with_keyword() {
+    assert_eq!(
+        motd_harness()
+            .render(
+                "v2",
+                "{{join settings.motd.[0] settings.motd.[1] sep=\"-\"}}",
+                json!(["a", "b"]),
+                None
+            )
+            .unwrap(),
+        "a-b"
+    );
+}
+
+#[test]
+fn test_run_stateful_and_closure_helpers() {
+    // When helpers are provided as struct instances or closures,
+    // Then they can be called like any other helper.
+    assert_eq!(
+        motd_harness()
+            .template_helper("v2", "abbreviate", vec![json!("hello")])
+            .unwrap(),
+        json!("hi")
+    );
+    assert_eq!(
+        motd_harness()
+            .template_helper("v2", "abbreviate", vec![json!("welcome")])
+            .unwrap(),
+        json!("welcome")
+    );
+    assert_eq!(
+        motd_harness()
+            .template_helper("v2", "count", vec![json!("a"), json!("b")])
+            .unwrap(),
+        json!(2)
+    );
+    assert_eq!(
+        motd_harness()
+            .render(
+                "v2",
+                "{{abbreviate settings.motd.[0]}} {{count 1 2 3}}",
+                json!(["goodbye"]),
+                None
+            )
+            .unwrap(),
+        "bye 3"
+    );
+}
+
+#[test]
+fn test_motdv2_set_with_patches() {
+    // When set is called with a JSON patch,
+    // Then the patched current value is set.
+    assert!(motd_harness()
+        .set_json_patch(
+            "v2",
+            json!([{"op": "add", "path": "/-", "value": "world"}]),
+            Some(json!(["hello"])),
         )
-        .unwrap(),
-        json!("two args? really??")
+        .is_ok());
+
+    // When set is called with a JSON merge patch,
+    // Then the merged value is set.
+    assert!(motd_harness()
+        .set_merge_patch("v2", json!(["hi"]), Some(json!(["hello"])))
+        .is_ok());
+
+    // When the patched value is not a valid motd,
+    // Then the set operation fails.
+    assert_extension_error!(
+        motd_harness().set_merge_patch("v2", json!({"motd": "hi"}), Some(json!(["hello"]))),
+        Set
+    );
+
+    // When a JSON patch cannot be applied to the current value,
+    // Then the set operation fails.
+    assert_extension_error!(
+        motd_harness().set_json_patch(
+            "v2",
+            json!([{"op": "remove", "path": "/3"}]),
+            Some(json!(["hello"])),
+        ),
+        ApplyJsonPatch
     );
+
+    // When a JSON patch is malformed,
+    // Then the set operation fails.
+    assert_extension_error!(
+        motd_harness().set_json_patch("v2", json!({"op": "add"}), None),
+        ParseJsonPatch
+    );
+}
+
+#[test]
+fn test_motdv2_set_requires_exactly_one_input() {
+    let set_with = |inputs: &[&str]| {
+        let args = ["extension", "proto1", "set", "--setting-version", "v2"]
+            .into_iter()
+            .chain(inputs.iter().copied());
+        motd_settings_extension().try_run_with_args(args)
+    };
+
+    // When set is called with no value and no patch,
+    // Then the set operation fails.
+    assert_extension_error!(set_with(&[]), ParseCLIArgs);
+
+    // When set is called with both a value and a patch,
+    // Then the set operation fails, even if the value is `null`.
+    assert_extension_error!(
+        set_with(&["--value", r#"["hi"]"#, "--merge-patch", r#"["hello"]"#]),
+        ParseCLIArgs
+    );
+    assert_extension_error!(
+        set_with(&["--value", "null", "--json-patch", "[]"]),
+        ParseCLIArgs
+    );
+
+    // When set is called with both kinds of patch,
+    // Then the set operation fails.
+    assert_extension_error!(
+        set_with(&["--merge-patch", r#"["hi"]"#, "--json-patch", "[]"]),
+        ParseCLIArgs
+    );
+
+    // When set is called with exactly one input,
+    // Then the set operation succeeds.
+    assert!(set_with(&["--value", r#"["hi"]"#]).is_ok());
+    assert!(set_with(&["--json-patch", "[]", "--current-value", r#"["hi"]"#]).is_ok());
 }
@@ -0,0 +1,219 @@
+use bottlerocket_settings_sdk::cli::proto1::Proto1Command;
+use bottlerocket_settings_sdk::extension::{CommandHook, HookError, SettingsExtensionError};
+use bottlerocket_settings_sdk::migrate::LinearMigratorModel;
+use bottlerocket_settings_sdk::testing::ExtensionHarness;
+use bottlerocket_settings_sdk::{
+    provide_template_helpers, BottlerocketSetting, GenerateResult, HelperError, LinearMigrator,
+    LinearMigratorExtensionBuilder, LinearlyMigrateable, NoMigration, SettingsExtension,
+    SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+
+type Result<T> = std::result::Result<T, Infallible>;
+
+/// A setting whose model code panics, as buggy extensions might.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PanickyV1(u32);
+
+/// A setting which migrates back to [`PanickyV1`] successfully.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PanickyV2(u32);
+
+impl SettingsModel for PanickyV1 {
+    type PartialKind = Self;
+    type ErrorKind = Infallible;
+
+    fn get_version() -> &'static str {
+        "v1"
+    }
+
+    fn set(_: Option<Self>, target: Self) -> Result<()> {
+        panic!("cannot set {}", target.0)
+    }
+
+    fn generate(
+        _: Option<Self::PartialKind>,
+        _: Option<Value>,
+    ) -> Result<GenerateResult<Self, Self>> {
+        panic!("generate exploded")
+    }
+
+    fn validate(_: Self, _: Option<Value>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl LinearlyMigrateable for PanickyV1 {
+    type ForwardMigrationTarget = PanickyV2;
+    type BackwardMigrationTarget = NoMigration;
+
+    fn migrate_forward(&self) -> Result<Self::ForwardMigrationTarget> {
+        std::panic::panic_any(self.0)
+    }
+
+    fn migrate_backward(&self) -> Result<Self::BackwardMigrationTarget> {
+        NoMigration::no_defined_migration()
+    }
+}
+
+impl SettingsModel for PanickyV2 {
+    type PartialKind = Self;
+    type ErrorKind = Infallible;
+
+    fn get_version() -> &'static str {
+        "v2"
+    }
+
+    fn set(_: Option<Self>, _: Self) -> Result<()> {
+        Ok(())
+    }
+
+    fn generate(
+        existing_partial: Option<Self::PartialKind>,
+        _: Option<Value>,
+    ) -> Result<GenerateResult<Self, Self>> {
+        Ok(GenerateResult::Complete(
+            existing_partial.unwrap_or_default(),
+        ))
+    }
+
+    fn validate(_: Self, _: Option<Value>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl LinearlyMigrateable for PanickyV2 {
+    type ForwardMigrationTarget = NoMigration;
+    type BackwardMigrationTarget = PanickyV1;
+
+    fn migrate_forward(&self) -> Result<Self::ForwardMigrationTarget> {
+        NoMigration::no_defined_migration()
+    }
+
+    fn migrate_backward(&self) -> Result<Self::BackwardMigrationTarget> {
+        Ok(PanickyV1(self.0))
+    }
+}
+
+fn panicky_extension() -> SettingsExtension<LinearMigrator, LinearMigratorModel> {
+    LinearMigratorExtensionBuilder::with_name("panicky")
+        .with_models(vec![
+            BottlerocketSetting::<PanickyV1>::model(),
+            BottlerocketSetting::<PanickyV2>::model(),
+        ])
+        .with_template_helpers(provide_template_helpers! {
+            "explode" => |_: Vec<Value>| -> std::result::Result<Value, HelperError> {
+                panic!("helper exploded")
+            },
+        })
+        .build()
+        .expect("Failed to build panicky settings extension")
+}
+
+fn panicky_harness(
+) -> ExtensionHarness<fn() -> SettingsExtension<LinearMigrator, LinearMigratorModel>> {
+    ExtensionHarness::new(panicky_extension)
+}
+
+#[test]
+fn test_model_panics_reported_as_errors() {
+    // When model code panics,
+    // Then the panic is reported as an error naming the operation and carrying its message.
+    let result = panicky_harness().set("v1", json!(7));
+    assert!(matches!(
+        &result,
+        Err(SettingsExtensionError::Panic { operation: "set", message }) if message == "cannot set 7"
+    ));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "set operation panicked: cannot set 7"
+    );
+
+    let result = panicky_harness().generate::<PanickyV1, PanickyV1>("v1", None, None);
+    assert!(matches!(
+        result,
+        Err(SettingsExtensionError::Panic { operation: "generate", message }) if message == "generate exploded"
+    ));
+
+    assert!(matches!(
+        panicky_harness().template_helper("v1", "explode", Vec::<Value>::new()),
+        Err(SettingsExtensionError::Panic {
+            operation: "helper",
+            ..
+        })
+    ));
+
+    // Operations which don't panic are unaffected.
+    assert!(panicky_harness().validate("v1", json!(7), None).is_ok());
+}
+
+#[test]
+fn test_migration_panics_reported_as_errors() {
+    // When a migration panics,
+    // Then the panic is reported as a migrate operation panic naming the sub-migration.
+    let result = panicky_harness().migrate(json!(7), "v1", "v2");
+    assert!(matches!(
+        &result,
+        Err(SettingsExtensionError::Panic { operation: "migrate", message })
+            if message == "Sub-migration of setting forward from 'v1' to 'v2' panicked: panic payload is not a string"
+    ));
+
+    // When a migration required by another command panics,
+    // Then it's also reported as a migrate operation panic.
+    assert!(matches!(
+        panicky_harness().validate_with_target_version("v1", "v2", json!(7), None),
+        Err(SettingsExtensionError::Panic {
+            operation: "migrate",
+            ..
+        })
+    ));
+
+    assert_eq!(
+        panicky_harness().migrate(json!(7), "v2", "v1").unwrap(),
+        json!(7)
+    );
+}
+
+/// A hook which panics before or after every command.
+struct PanickyHook {
+    before: bool,
+}
+
+impl CommandHook for PanickyHook {
+    fn before(&self, _: &Proto1Command) -> std::result::Result<(), HookError> {
+        if self.before {
+            panic!("hook exploded before");
+        }
+        Ok(())
+    }
+
+    fn after(&self, _: &Proto1Command, _: std::result::Result<&str, &dyn std::error::Error>) {
+        if !self.before {
+            panic!("hook exploded after");
+        }
+    }
+}
+
+#[test]
+fn test_hook_panics_reported_as_errors() {
+    // When a command hook panics,
+    // Then the panic is reported as an error of the command's operation.
+    for before in [true, false] {
+        let harness = ExtensionHarness::new(move || {
+            LinearMigratorExtensionBuilder::with_name("panicky")
+                .with_models(vec![BottlerocketSetting::<PanickyV2>::model()])
+                .with_hook(PanickyHook { before })
+                .build()
+                .unwrap()
+        });
+
+        let expected = if before { "before" } else { "after" };
+        assert!(matches!(
+            harness.validate("v2", json!(7), None),
+            Err(SettingsExtensionError::Panic { operation: "validate", message })
+                if message == format!("in command hook: hook exploded {expected}")
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+use bottlerocket_settings_sdk::migrate::LinearMigratorModel;
+use bottlerocket_settings_sdk::model::redact::{deserialize_redacted, redact_value, REDACTED};
+use bottlerocket_settings_sdk::testing::ExtensionHarness;
+use bottlerocket_settings_sdk::{
+    BottlerocketSetting, GenerateResult, LinearMigrator, LinearMigratorExtensionBuilder,
+    LinearlyMigrateable, NoMigration, SettingsExtension, SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::convert::Infallible;
+
+/// A setting containing credentials, which must not appear in logs or errors.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Credentials {
+    username: String,
+    password: String,
+    #[serde(default)]
+    tokens: Vec<String>,
+}
+
+type Result<T> = std::result::Result<T, Infallible>;
+
+impl SettingsModel for Credentials {
+    type PartialKind = Self;
+    type ErrorKind = Infallible;
+
+    fn get_version() -> &'static str {
+        "v1"
+    }
+
+    fn sensitive_fields() -> Vec<&'static str> {
+        vec!["/password", "/tokens/*"]
+    }
+
+    fn set(_: Option<Self>, _: Self) -> Result<()> {
+        Ok(())
+    }
+
+    fn generate(
+        existing_partial: Option<Self::PartialKind>,
+        _: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<Self::PartialKind, Self>> {
+        Ok(GenerateResult::Complete(
+            existing_partial.unwrap_or_default(),
+        ))
+    }
+
+    fn validate(_: Self, _: Option<serde_json::Value>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl LinearlyMigrateable for Credentials {
+    type ForwardMigrationTarget = NoMigration;
+    type BackwardMigrationTarget = NoMigration;
+
+    fn migrate_forward(&self) -> Result<Self::ForwardMigrationTarget> {
+        NoMigration::no_defined_migration()
+    }
+
+    fn migrate_backward(&self) -> Result<Self::BackwardMigrationTarget> {
+        NoMigration::no_defined_migration()
+    }
+}
+
+fn credentials_extension() -> SettingsExtension<LinearMigrator, LinearMigratorModel> {
+    LinearMigratorExtensionBuilder::with_name("credentials")
+        .with_models(vec![BottlerocketSetting::<Credentials>::model()])
+        .build()
+        .expect("Failed to build credentials settings extension")
+}
+
+fn credentials_harness(
+) -> ExtensionHarness<fn() -> SettingsExtension<LinearMigrator, LinearMigratorModel>> {
+    ExtensionHarness::new(credentials_extension)
+}
+
+#[test]
+fn test_sensitive_input_redacted_from_errors() {
+    // When a value containing sensitive fields fails to deserialize,
+    // Then the sensitive values are redacted from the error.
+    let err = credentials_harness()
+        .set(
+            "v1",
+            json!({"username": 42, "password": "hunter2", "tokens": ["abc123"]}),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(!err.contains("hunter2"), "{}", err);
+    assert!(!err.contains("abc123"), "{}", err);
+    assert!(err.contains(REDACTED), "{}", err);
+    assert!(err.contains("42"), "{}", err);
+
+    // When the sensitive value itself is invalid,
+    // Then it's redacted from the deserialization error's message as well.
+    let err = credentials_harness()
+        .validate(
+            "v1",
+            json!({"username": "admin", "password": 8675309}),
+            None,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(!err.contains("8675309"), "{}", err);
+    assert!(err.contains("admin"), "{}", err);
+}
+
+#[test]
+fn test_short_sensitive_values_do_not_mangle_errors() {
+    // When a value containing a short sensitive value fails to deserialize elsewhere,
+    // Then the error is reported intact, without the sensitive value.
+    let err = credentials_harness()
+        .set(
+            "v1",
+            json!({"username": 42, "password": "e", "tokens": ["4"]}),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("invalid type: integer `42`, expected a string"),
+        "{}",
+        err
+    );
+    assert!(!err.contains(r#""e""#), "{}", err);
+    assert!(!err.contains(r#""4""#), "{}", err);
+}
+
+/// An account whose PIN is sensitive, but is not a string.
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Account {
+    pin: u32,
+    name: String,
+    tokens: Vec<String>,
+}
+
+#[test]
+fn test_redacted_errors_report_the_offending_value() {
+    let sensitive_fields = ["/pin", "/tokens/*"];
+
+    // When a value fails to deserialize at a field which is not sensitive,
+    // Then the original error is reported, rather than one about a redacted field.
+    let err = deserialize_redacted::<Account>(
+        json!({"pin": 1234, "name": 42, "tokens": []}),
+        &sensitive_fields,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, "invalid type: integer `42`, expected a string");
+
+    // When a sensitive value fails to deserialize,
+    // Then the error is redacted, but still gives the path of the offending value.
+    let err = deserialize_redacted::<Account>(
+        json!({"pin": "hunter2", "name": "admin", "tokens": []}),
+        &sensitive_fields,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, format!("invalid value at 'pin': {REDACTED}"));
+
+    let err = deserialize_redacted::<Account>(
+        json!({"pin": 1234, "name": "admin", "tokens": ["abc", 8675309]}),
+        &sensitive_fields,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(!err.contains("8675309"), "{}", err);
+    assert!(err.contains("tokens[1]"), "{}", err);
+}
+
+#[test]
+fn test_redact_value() {
+    let value = json!({
+        "password": "hunter2",
+        "tokens": ["abc", "def"],
+        "nested": {"a/b": {"key": "secret"}, "items": [{"key": 1}, {"key": 2}]},
+    });
+
+    assert_eq!(
+        redact_value(
+            &value,
+            &["/nested/a~1b/key", "/nested/items/*/key", "/missing"]
+        ),
+        json!({
+            "password": "hunter2",
+            "tokens": ["abc", "def"],
+            "nested": {"a/b": {"key": REDACTED}, "items": [{"key": REDACTED}, {"key": REDACTED}]},
+        })
+    );
+    assert_eq!(
+        redact_value(&value, &["/tokens/1"]),
+        json!({
+            "password": "hunter2",
+            "tokens": ["abc", REDACTED],
+            "nested": {"a/b": {"key": "secret"}, "items": [{"key": 1}, {"key": 2}]},
+        })
+    );
+    assert_eq!(redact_value(&value, &[""]), json!(REDACTED));
+}
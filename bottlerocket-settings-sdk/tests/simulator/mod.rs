@@ -0,0 +1,342 @@
+use anyhow::{anyhow, Result};
+use bottlerocket_settings_sdk::simulator::{
+    BinaryExtension, GenerationRound, GenerationSimulatorBuilder, InProcessExtension,
+    SimulationOutcome, SimulatorError,
+};
+use bottlerocket_settings_sdk::{
+    client::ExtensionClient, BottlerocketSetting, GenerateResult, LinearMigrator,
+    LinearMigratorExtensionBuilder, LinearMigratorModel, LinearlyMigrateable, NoMigration,
+    SettingsExtension, SettingsModel,
+};
+use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// A hostname which is always generated immediately.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hostname(String);
+
+/// A greeting which is generated once the hostname is known.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Greeting(String);
+
+/// A setting which never receives the data it needs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stuck(String);
+
+/// A setting which keeps making progress on its partial value, but never completes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Counter(u64);
+
+/// A setting whose partial value alternates between two values without completing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Flip(bool);
+
+/// A setting whose partial value rotates between three values without completing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Rotate(u64);
+
+/// A setting which fails to generate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Broken;
+
+macro_rules! define_model {
+    ($name:ident, $partial:ty, $generate:expr) => {
+        impl SettingsModel for $name {
+            type PartialKind = $partial;
+            type ErrorKind = anyhow::Error;
+
+            fn get_version() -> &'static str {
+                "v1"
+            }
+
+            fn set(_: Option<Self>, _: Self) -> Result<()> {
+                Ok(())
+            }
+
+            fn generate(
+                existing_partial: Option<Self::PartialKind>,
+                dependent_settings: Option<serde_json::Value>,
+            ) -> Result<GenerateResult<Self::PartialKind, Self>> {
+                $generate(existing_partial, dependent_settings.unwrap_or_default())
+            }
+
+            fn validate(_: Self, _: Option<serde_json::Value>) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        impl LinearlyMigrateable for $name {
+            type ForwardMigrationTarget = NoMigration;
+            type BackwardMigrationTarget = NoMigration;
+
+            fn migrate_forward(&self) -> Result<NoMigration> {
+                NoMigration::no_defined_migration()
+            }
+
+            fn migrate_backward(&self) -> Result<NoMigration> {
+                NoMigration::no_defined_migration()
+            }
+        }
+    };
+}
+
+define_model!(Hostname, Self, |_, _| Ok(GenerateResult::Complete(
+    Hostname("localhost".to_string())
+)));
+
+define_model!(Greeting, Self, |_, settings: serde_json::Value| Ok(
+    match settings["hostname"].as_str() {
+        Some(hostname) => GenerateResult::Complete(Greeting(format!("Welcome to {hostname}!"))),
+        None => GenerateResult::NeedsData(None),
+    }
+));
+
+define_model!(Stuck, Self, |_, settings: serde_json::Value| Ok(
+    match settings["nonexistent"].as_str() {
+        Some(value) => GenerateResult::Complete(Stuck(value.to_string())),
+        None => GenerateResult::NeedsData(None),
+    }
+));
+
+define_model!(Counter, Self, |partial: Option<Counter>, _| Ok(
+    GenerateResult::NeedsData(Some(Counter(partial.map(|c| c.0 + 1).unwrap_or_default())))
+));
+
+define_model!(Flip, Self, |partial: Option<Flip>, _| Ok(
+    GenerateResult::NeedsData(Some(Flip(partial.map(|f| !f.0).unwrap_or_default())))
+));
+
+define_model!(Rotate, Self, |partial: Option<Rotate>, _| Ok(
+    GenerateResult::NeedsData(Some(Rotate(
+        partial.map(|r| (r.0 + 1) % 3).unwrap_or_default()
+    )))
+));
+
+define_model!(Broken, Self, |_, _| Err(anyhow!("generation is broken")));
+
+fn extension<T>(name: &'static str) -> SettingsExtension<LinearMigrator, LinearMigratorModel>
+where
+    T: SettingsModel + LinearlyMigrateable + 'static,
+{
+    LinearMigratorExtensionBuilder::with_name(name)
+        .with_models(vec![BottlerocketSetting::<T>::model()])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_dependent_settings_complete() {
+    // Given a setting which depends on another setting,
+    // When generation is simulated,
+    // Then the dependent setting completes in a later round.
+    let greeting = extension::<Greeting>("greeting");
+    let hostname = extension::<Hostname>("hostname");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&greeting, "v1"))
+        .with_extension(InProcessExtension::new(&hostname, "v1"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert_eq!(report.outcome, SimulationOutcome::Complete);
+    assert_eq!(
+        report.rounds,
+        vec![
+            GenerationRound {
+                completed: vec!["hostname".to_string()],
+                needs_data: vec!["greeting".to_string()],
+            },
+            GenerationRound {
+                completed: vec!["greeting".to_string()],
+                needs_data: vec![],
+            },
+        ]
+    );
+    assert_eq!(
+        report.settings,
+        btreemap! {
+            "greeting".to_string() => json!("Welcome to localhost!"),
+            "hostname".to_string() => json!("localhost"),
+        }
+    );
+    assert!(report.partials.is_empty());
+}
+
+#[test]
+fn test_livelock_detected() {
+    // Given a setting which depends on a setting that is never generated,
+    // When generation is simulated,
+    // Then the simulation stops once a round makes no progress.
+    let hostname = extension::<Hostname>("hostname");
+    let stuck = extension::<Stuck>("stuck");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&hostname, "v1"))
+        .with_extension(InProcessExtension::new(&stuck, "v1"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert_eq!(
+        report.outcome,
+        SimulationOutcome::Livelock {
+            incomplete: vec!["stuck".to_string()]
+        }
+    );
+    assert_eq!(report.rounds.len(), 2);
+    assert_eq!(report.partials, btreemap! { "stuck".to_string() => None });
+}
+
+#[test]
+fn test_round_limit() {
+    // Given a setting which changes its partial value every round without completing,
+    // When generation is simulated,
+    // Then the simulation stops at the maximum number of rounds.
+    let counter = extension::<Counter>("counter");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&counter, "v1"))
+        .with_max_rounds(5)
+        .build()
+        .unwrap()
+        .run();
+
+    assert_eq!(
+        report.outcome,
+        SimulationOutcome::RoundLimit {
+            incomplete: vec!["counter".to_string()]
+        }
+    );
+    assert_eq!(report.rounds.len(), 5);
+    assert_eq!(
+        report.partials,
+        btreemap! { "counter".to_string() => Some(json!(4)) }
+    );
+}
+
+#[test]
+fn test_cycle_detected() {
+    // Given settings whose partial values alternate without completing,
+    // When generation is simulated,
+    // Then the simulation stops once the settings return to an earlier state.
+    let flip = extension::<Flip>("flip");
+    let rotate = extension::<Rotate>("rotate");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&flip, "v1"))
+        .with_extension(InProcessExtension::new(&rotate, "v1"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert_eq!(
+        report.outcome,
+        SimulationOutcome::Cycle {
+            incomplete: vec!["flip".to_string(), "rotate".to_string()],
+            period: 6,
+        }
+    );
+    assert_eq!(report.rounds.len(), 7);
+    assert_eq!(
+        report.partials,
+        btreemap! {
+            "flip".to_string() => Some(json!(false)),
+            "rotate".to_string() => Some(json!(0)),
+        }
+    );
+}
+
+#[test]
+fn test_generation_failure() {
+    // Given a setting which fails to generate,
+    // When generation is simulated,
+    // Then the failure is reported.
+    let broken = extension::<Broken>("broken");
+    let hostname = extension::<Hostname>("hostname");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&hostname, "v1"))
+        .with_extension(InProcessExtension::new(&broken, "v1"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert!(matches!(
+        report.outcome,
+        SimulationOutcome::Failed { ref extension, .. } if extension == "broken"
+    ));
+    assert_eq!(report.settings.len(), 1);
+}
+
+#[test]
+fn test_missing_version_fails() {
+    // Given an extension simulated with a version it does not provide,
+    // When generation is simulated,
+    // Then the failure is reported.
+    let hostname = extension::<Hostname>("hostname");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&hostname, "v2"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert!(matches!(report.outcome, SimulationOutcome::Failed { .. }));
+}
+
+#[test]
+fn test_binary_extension() {
+    // Given an extension binary which depends on an in-process extension,
+    // When generation is simulated,
+    // Then the binary receives the generated settings.
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("simulated-greeting-extension");
+    std::fs::write(
+        &path,
+        r#"#!/bin/sh
+case "$*" in
+    *localhost*) echo '{"Complete": "Welcome!"}' ;;
+    *) echo '{"NeedsData": null}' ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let hostname = extension::<Hostname>("hostname");
+
+    let report = GenerationSimulatorBuilder::new()
+        .with_extension(BinaryExtension::new(
+            "greeting",
+            "v1",
+            ExtensionClient::new(path),
+        ))
+        .with_extension(InProcessExtension::new(&hostname, "v1"))
+        .build()
+        .unwrap()
+        .run();
+
+    assert_eq!(report.outcome, SimulationOutcome::Complete);
+    assert_eq!(report.settings["greeting"], json!("Welcome!"));
+}
+
+#[test]
+fn test_duplicate_extensions_rejected() {
+    // Given two extensions which generate settings with the same name,
+    // When the simulator is built,
+    // Then the duplicate is rejected.
+    let hostname = extension::<Hostname>("hostname");
+    let other_hostname = extension::<Stuck>("hostname");
+
+    let result = GenerationSimulatorBuilder::new()
+        .with_extension(InProcessExtension::new(&hostname, "v1"))
+        .with_extension(InProcessExtension::new(&other_hostname, "v1"))
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(SimulatorError::DuplicateExtension { ref name }) if name == "hostname"
+    ));
+}
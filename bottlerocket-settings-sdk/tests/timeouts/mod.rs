@@ -0,0 +1,144 @@
+use bottlerocket_settings_sdk::cli::proto1::Proto1Command;
+use bottlerocket_settings_sdk::extension::{CommandHook, SettingsExtensionError};
+use bottlerocket_settings_sdk::migrate::null::NullMigrator;
+use bottlerocket_settings_sdk::model::TypeErasedModel;
+use bottlerocket_settings_sdk::{
+    BottlerocketSetting, GenerateResult, NullMigratorExtensionBuilder, SettingsExtension,
+    SettingsModel,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::process::{Command, ExitCode};
+use std::time::Duration;
+
+/// The environment variable containing the CLI arguments that `serve_slow_extension` should run.
+const SLOW_EXTENSION_ARGS: &str = "SLOW_EXTENSION_ARGS";
+
+/// A setting which takes far too long to generate, as if waiting on a file that never appears.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SlowSetting(String);
+
+impl SettingsModel for SlowSetting {
+    type PartialKind = Self;
+    type ErrorKind = Infallible;
+
+    fn get_version() -> &'static str {
+        "v1"
+    }
+
+    fn set(_: Option<Self>, _: Self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn generate(
+        _: Option<Self::PartialKind>,
+        _: Option<serde_json::Value>,
+    ) -> Result<GenerateResult<Self, Self>, Infallible> {
+        std::thread::sleep(Duration::from_secs(60));
+        Ok(GenerateResult::Complete(Self::default()))
+    }
+
+    fn validate(_: Self, _: Option<serde_json::Value>) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Records the result of each command on stderr, as an audit hook might.
+struct StderrAudit;
+
+impl CommandHook for StderrAudit {
+    fn after(&self, command: &Proto1Command, result: Result<&str, &dyn std::error::Error>) {
+        match result {
+            Ok(_) => eprintln!("audit: {} succeeded", command.name()),
+            Err(e) => eprintln!("audit: {} failed: {}", command.name(), e),
+        }
+    }
+}
+
+fn slow_extension() -> SettingsExtension<NullMigrator, Box<dyn TypeErasedModel>> {
+    NullMigratorExtensionBuilder::with_name("slow")
+        .with_models(vec![BottlerocketSetting::<SlowSetting>::model()])
+        .with_timeout(Duration::from_secs(60))
+        .with_command_timeout("generate", Duration::from_millis(100))
+        .with_hook(StderrAudit)
+        .build()
+        .expect("Failed to build slow settings extension")
+}
+
+/// Serves the slow extension when this test binary is run as a subprocess by the tests below.
+///
+/// Time limits are only enforced for extensions which own their process, so the tests run the
+/// extension from a copy of the test process.
+#[test]
+fn serve_slow_extension() -> ExitCode {
+    match std::env::var(SLOW_EXTENSION_ARGS) {
+        Ok(args) => {
+            let args: Vec<String> = serde_json::from_str(&args).unwrap();
+            slow_extension().run_with_args(args)
+        }
+        Err(_) => ExitCode::SUCCESS,
+    }
+}
+
+/// Runs the slow extension in a subprocess, returning whether it succeeded, its stdout and its
+/// stderr.
+fn run_slow_extension(args: &[&str]) -> (bool, String, String) {
+    let args: Vec<&str> = ["slow", "proto1"].iter().chain(args).copied().collect();
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "timeouts::serve_slow_extension", "--nocapture"])
+        .env(SLOW_EXTENSION_ARGS, serde_json::to_string(&args).unwrap())
+        .output()
+        .unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_command_timeout() {
+    // When a command exceeds its time limit,
+    // Then the extension reports a timeout error to its hooks and the caller, and exits.
+    let (success, stdout, stderr) = run_slow_extension(&["generate", "--setting-version", "v1"]);
+    assert!(!success);
+    assert!(
+        stdout.contains("generate operation timed out after 100ms"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stderr.contains("audit: generate failed: generate operation timed out after 100ms"),
+        "{}",
+        stderr
+    );
+
+    // When a command completes within its time limit,
+    // Then the extension's output is unaffected.
+    let (success, stdout, stderr) =
+        run_slow_extension(&["validate", "--setting-version", "v1", "--value", "\"fast\""]);
+    assert!(success, "{}", stdout);
+    assert!(!stdout.contains("timed out"), "{}", stdout);
+    assert_eq!(
+        stderr.matches("audit: validate succeeded").count(),
+        1,
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_unknown_command_timeout_rejected() {
+    // When a time limit is set for a command which doesn't exist,
+    // Then the extension fails to build.
+    let result = NullMigratorExtensionBuilder::with_name("slow")
+        .with_models(vec![BottlerocketSetting::<SlowSetting>::model()])
+        .with_command_timeout("generat", Duration::from_millis(100))
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(SettingsExtensionError::UnknownTimeoutCommand { ref command }) if command == "generat"
+    ));
+}
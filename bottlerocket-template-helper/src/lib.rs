@@ -2,26 +2,57 @@
 //! See the documentation in [`bottlerocket-settings-sdk::helper`] for more information.
 use darling::{ast::NestedMeta, FromMeta};
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{self, FnArg, ItemFn};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{
+    self, FnArg, GenericArgument, ImplItem, ImplItemFn, Item, ItemFn, ItemImpl, PathArguments,
+    ReturnType, Signature, Type,
+};
 
 #[derive(FromMeta)]
 struct MacroArgs {
     ident: syn::Ident,
-    vis: Option<String>,
+    vis: Option<syn::LitStr>,
 }
 
 /// Defines a [`bottlerocket-settings-sdk::helper::HelperDef`] based on a given function.
 ///
 /// This macro requires:
-/// * Your function arguments implement [`serde::Deserialize`]
-/// * Your return value is a `Result<T, E>` where `T` implements [`serde::Serialize`]
-///   and `E` implements `Into<Box<dyn std::error::Error>>`.
+/// * Your function arguments implement [`serde::Deserialize`], or are references to such types.
+///   `&str` and `&[T]` arguments are deserialized into a `String` or `Vec<T>` and then borrowed.
+/// * Your return value implements [`serde::Serialize`], or is a `Result<T, E>` where `T`
+///   implements [`serde::Serialize`] and `E` implements `Into<Box<dyn std::error::Error>>`.
+///   Functions may also return `impl Serialize`.
+///
+/// Functions may have lifetime parameters, but not generic type parameters, since arguments must
+/// be deserialized into concrete types.
+///
+/// Trailing parameters of type `Option<T>` are optional, and are `None` when not given. The last
+/// parameter may be marked `#[variadic]` to collect all remaining arguments; its type must
+/// deserialize from a JSON array, such as `Vec<T>`.
+///
+/// Parameters marked `#[keyword]` are taken from the helper's named (hash) arguments, using the
+/// parameter's name, rather than positionally. Keyword parameters of type `Option<T>` are
+/// optional.
+///
+/// A parameter of type `HelperContext` marked `#[helper_context]` receives the context of the
+/// setting that the helper is called for, such as the setting's current value. It is not passed
+/// from templates. The helper fails with `HelperError::MissingContext` if it is called without a
+/// context, such as through the positional `helper_fn`; an `Option<HelperContext>` parameter is
+/// `None` instead.
+///
+/// The macro generates a unit struct named by `ident`, which is a `HelperDef` that accepts
+/// positional and named arguments and the helper's context, and describes the function's parameter
+/// names and types, return type, and doc comment via `HelperDef::signature`. The struct can also be
+/// called like a function with a list of JSON values.
 ///
 /// To define a `HelperDef` called `my_helper` based on a function, you could do something like:
 ///
 /// ```
 /// use bottlerocket_settings_sdk::helper::{HelperDef, template_helper};
+/// use serde_json::json;
 ///
 /// #[template_helper(ident = my_helper)]
 /// fn help_with(list_of_things: Vec<String>) -> Result<Vec<String>, anyhow::Error> {
@@ -30,75 +61,943 @@ struct MacroArgs {
 ///         .map(|s| format!("Helped with '{s}'!"))
 ///         .collect())
 /// }
+///
+/// #[template_helper(ident = join_helper)]
+/// fn join(#[variadic] items: &[String], #[keyword] sep: Option<&str>) -> String {
+///     items.join(sep.unwrap_or_default())
+/// }
+///
+/// assert_eq!(my_helper(vec![json!(["a"])]).unwrap(), json!(["Helped with 'a'!"]));
+/// assert_eq!(join_helper.signature().unwrap().params[1].name, "sep");
+/// ```
+///
+/// # Stateful Helpers
+///
+/// Helpers which need state, such as a lookup table or loaded configuration, can be defined as a
+/// method on a struct. Placing the macro on the struct's `impl` block, without arguments,
+/// implements `HelperDef` for the struct using the method which takes `&self`. If the block
+/// contains more than one such method, the helper method must be marked `#[helper]`.
+///
+/// ```
+/// use bottlerocket_settings_sdk::helper::{HelperDef, template_helper};
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// struct Lookup {
+///     table: HashMap<String, String>,
+/// }
+///
+/// #[template_helper]
+/// impl Lookup {
+///     /// Looks up a key in the table.
+///     fn lookup(&self, key: String) -> Result<Option<String>, anyhow::Error> {
+///         Ok(self.table.get(&key).cloned())
+///     }
+/// }
+///
+/// let helper = Lookup {
+///     table: HashMap::from([("a".to_string(), "b".to_string())]),
+/// };
+/// assert_eq!(helper.helper_fn(vec![json!("a")]).unwrap(), json!("b"));
 /// ```
 #[proc_macro_attribute]
 pub fn template_helper(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args: MacroArgs =
-        MacroArgs::from_list(&NestedMeta::parse_meta_list(args.into()).unwrap()).unwrap();
+    expand(args.into(), input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expands the macro, returning an error pointing at the offending code if its input is invalid.
+fn expand(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    match syn::parse2(input)? {
+        Item::Fn(item_fn) => fn_helper(args, item_fn),
+        Item::Impl(item_impl) if args.is_empty() => impl_helper(item_impl),
+        Item::Impl(_) => Err(syn::Error::new_spanned(
+            args,
+            "template_helper macro does not take arguments when used on an impl block",
+        )),
+        item => Err(syn::Error::new_spanned(
+            item,
+            "template_helper macro may only be used on functions and impl blocks",
+        )),
+    }
+}
 
-    let helper_fn_name = args.ident;
+/// Generates a unit struct named by the macro's `ident` argument, which implements `HelperDef` by
+/// calling the given function, and which may be called like a function with a list of JSON values.
+fn fn_helper(args: TokenStream2, mut fn_ast: ItemFn) -> syn::Result<TokenStream2> {
+    let args = MacroArgs::from_list(&NestedMeta::parse_meta_list(args)?)?;
 
-    let fn_ast: ItemFn = syn::parse2(input.into()).unwrap();
+    let helper_name = args.ident;
     let fn_name = fn_ast.sig.ident.clone();
+    let helper = HelperCodegen::new(&mut fn_ast.sig, &fn_ast.attrs, quote! { #fn_name }, false)?;
+    let body = &helper.body;
+    let signature = &helper.signature;
+    let helper_doc = format!("The template helper generated for [`{}`].", fn_name);
 
-    let num_args = fn_ast.sig.inputs.len();
-    let arg_types: Vec<Box<syn::Type>> = fn_ast
-        .sig
-        .inputs
-        .iter()
-        .map(|arg| match arg {
-            FnArg::Receiver(_) => {
-                panic!("template_helper macro does not work on methods that take `self`")
+    let vis: syn::Visibility = match args.vis {
+        Some(visibility) => visibility.parse().map_err(|_| {
+            syn::Error::new_spanned(
+                &visibility,
+                format!("invalid visibility `{}`", visibility.value()),
+            )
+        })?,
+        None => syn::Visibility::Inherited,
+    };
+
+    Ok(quote! {
+        #fn_ast
+
+        #[doc = #helper_doc]
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #helper_name;
+
+        // Dereferencing to a function pointer allows the helper to be called like a function.
+        impl std::ops::Deref for #helper_name {
+            type Target = fn(
+                Vec<serde_json::Value>,
+            ) -> std::result::Result<serde_json::Value, bottlerocket_settings_sdk::HelperError>;
+
+            fn deref(&self) -> &Self::Target {
+                const HELPER_FN: fn(
+                    Vec<serde_json::Value>,
+                ) -> std::result::Result<
+                    serde_json::Value,
+                    bottlerocket_settings_sdk::HelperError,
+                > = |args| bottlerocket_settings_sdk::HelperDef::helper_fn(&#helper_name, args);
+                &HELPER_FN
+            }
+        }
+
+        impl bottlerocket_settings_sdk::HelperDef for #helper_name {
+            fn helper_fn(
+                &self,
+                args: Vec<serde_json::Value>,
+            ) -> std::result::Result<serde_json::Value, bottlerocket_settings_sdk::HelperError> {
+                self.execute(bottlerocket_settings_sdk::helper::HelperInvocation::new(args))
+            }
+
+            fn execute(
+                &self,
+                mut invocation: bottlerocket_settings_sdk::helper::HelperInvocation,
+            ) -> std::result::Result<serde_json::Value, bottlerocket_settings_sdk::HelperError> {
+                #body
+            }
+
+            fn signature(&self) -> Option<bottlerocket_settings_sdk::helper::HelperSignature> {
+                Some(#signature)
             }
-            FnArg::Typed(t) => t.ty.clone(),
+        }
+    })
+}
+
+/// Implements `HelperDef` for the type of an impl block by calling the block's helper method.
+fn impl_helper(mut item_impl: ItemImpl) -> syn::Result<TokenStream2> {
+    let method = helper_method(&mut item_impl)?;
+    let method_name = method.sig.ident.clone();
+    let helper = HelperCodegen::new(
+        &mut method.sig,
+        &method.attrs,
+        quote! { self.#method_name },
+        true,
+    )?;
+    let body = &helper.body;
+    let signature = &helper.signature;
+
+    let self_ty = &item_impl.self_ty;
+    let (impl_generics, _, where_clause) = item_impl.generics.split_for_impl();
+
+    Ok(quote! {
+        #item_impl
+
+        impl #impl_generics bottlerocket_settings_sdk::HelperDef for #self_ty #where_clause {
+            fn helper_fn(
+                &self,
+                args: Vec<serde_json::Value>,
+            ) -> std::result::Result<serde_json::Value, bottlerocket_settings_sdk::HelperError> {
+                self.execute(bottlerocket_settings_sdk::helper::HelperInvocation::new(args))
+            }
+
+            fn execute(
+                &self,
+                mut invocation: bottlerocket_settings_sdk::helper::HelperInvocation,
+            ) -> std::result::Result<serde_json::Value, bottlerocket_settings_sdk::HelperError> {
+                #body
+            }
+
+            fn signature(&self) -> Option<bottlerocket_settings_sdk::helper::HelperSignature> {
+                Some(#signature)
+            }
+        }
+    })
+}
+
+/// Finds the method of an impl block which implements the helper, removing its `#[helper]` marker.
+///
+/// The helper method is the one marked `#[helper]`, or otherwise the only method taking `self`.
+fn helper_method(item_impl: &mut ItemImpl) -> syn::Result<&mut ImplItemFn> {
+    let mut methods: Vec<&mut ImplItemFn> = item_impl
+        .items
+        .iter_mut()
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) => Some(method),
+            _ => None,
         })
         .collect();
 
-    let mut helper_fn: ItemFn = syn::parse2(quote! {
-        fn #helper_fn_name(
-            args: Vec<serde_json::Value>,
-        ) -> std::result::Result<
-            serde_json::Value,
-            bottlerocket_settings_sdk::HelperError
-        > {
-            if args.len() != #num_args {
+    let marked: Vec<bool> = methods
+        .iter_mut()
+        .map(|method| take_marker(&mut method.attrs, "helper"))
+        .collect();
+    let candidates: Vec<usize> = match marked.contains(&true) {
+        true => (0..methods.len()).filter(|&i| marked[i]).collect(),
+        false => (0..methods.len())
+            .filter(|&i| methods[i].sig.receiver().is_some())
+            .collect(),
+    };
+
+    match candidates[..] {
+        [i] => Ok(methods.swap_remove(i)),
+        [] => Err(syn::Error::new_spanned(
+            &item_impl.self_ty,
+            "template_helper macro requires the impl block to have a method taking `&self`",
+        )),
+        _ => Err(syn::Error::new_spanned(
+            &methods[candidates[1]].sig,
+            "template_helper macro requires exactly one helper method in the impl block; mark it with #[helper]",
+        )),
+    }
+}
+
+/// The generated code which calls a helper function and describes it.
+struct HelperCodegen {
+    /// Statements which call the helper function with arguments taken from `invocation`.
+    body: TokenStream2,
+    /// An expression which builds the function's `HelperSignature`.
+    signature: TokenStream2,
+}
+
+impl HelperCodegen {
+    /// Generates code which calls the function with the given signature via `callee`, removing
+    /// any parameter markers from the signature.
+    fn new(
+        sig: &mut Signature,
+        attrs: &[syn::Attribute],
+        callee: TokenStream2,
+        is_method: bool,
+    ) -> syn::Result<Self> {
+        let helper_args = helper_args(sig, is_method)?;
+        let uses_context = helper_args.iter().any(|arg| arg.kind == ArgKind::Context);
+        // The context is not an argument from templates, so it's left out of the signature.
+        let params: Vec<&HelperArg> = helper_args
+            .iter()
+            .filter(|arg| arg.kind != ArgKind::Context)
+            .collect();
+        let param_names: Vec<&String> = params.iter().map(|arg| &arg.name).collect();
+        let param_types: Vec<TokenStream2> =
+            params.iter().map(|arg| helper_type(&arg.ty)).collect();
+        let optional: Vec<bool> = params
+            .iter()
+            .map(|arg| matches!(arg.kind, ArgKind::Optional | ArgKind::OptionalKeyword))
+            .collect();
+        let variadic: Vec<bool> = params
+            .iter()
+            .map(|arg| arg.kind == ArgKind::Variadic)
+            .collect();
+        let keyword: Vec<bool> = params.iter().map(|arg| arg.is_keyword()).collect();
+        let keyword_names: Vec<&String> = params
+            .iter()
+            .filter(|arg| arg.is_keyword())
+            .map(|arg| &arg.name)
+            .collect();
+
+        let min_args = params
+            .iter()
+            .filter(|arg| arg.kind == ArgKind::Required)
+            .count();
+        let max_args = (!variadic.contains(&true))
+            .then(|| params.iter().filter(|arg| arg.is_positional()).count());
+        let max_args_tokens = match max_args {
+            Some(max_args) => quote! { Some(#max_args) },
+            None => quote! { None },
+        };
+        let arity_check = match max_args {
+            Some(max_args) if max_args == min_args => quote! { args.len() != #min_args },
+            Some(max_args) if min_args == 0 => quote! { args.len() > #max_args },
+            Some(max_args) => quote! { args.len() < #min_args || args.len() > #max_args },
+            None if min_args == 0 => quote! { false },
+            None => quote! { args.len() < #min_args },
+        };
+        if let Some(param) = sig
+            .generics
+            .params
+            .iter()
+            .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+        {
+            return Err(syn::Error::new_spanned(
+                param,
+                "template_helper functions may not have generic type or const parameters, as helper arguments must be deserialized into concrete types",
+            ));
+        }
+
+        let (arg_bindings, arg_values): (Vec<TokenStream2>, Vec<TokenStream2>) = helper_args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| arg_binding(arg, &quote::format_ident!("helper_arg_{}", i)))
+            .unzip();
+
+        let helper_return = helper_return(sig)?;
+        let return_type = helper_type(&helper_return.ty);
+        // Infallible helpers have their return value serialized directly.
+        let call = quote! { #callee(#(#arg_values),*) };
+        let call = match helper_return.fallible {
+            true => quote! {
+                #call
+                    .map_err(|e| bottlerocket_settings_sdk::HelperError::HelperExecute {
+                        source: e.into(),
+                    })
+                    .and_then(|result| serde_json::to_value(result).map_err(|e| {
+                        bottlerocket_settings_sdk::HelperError::JSONSerialize { source: e }
+                    }))
+            },
+            false => quote! {
+                serde_json::to_value(#call).map_err(|e| {
+                    bottlerocket_settings_sdk::HelperError::JSONSerialize { source: e }
+                })
+            },
+        };
+        let fn_name_str = sig.ident.to_string();
+        let doc = match doc_comment(attrs) {
+            Some(doc) => quote! { Some(#doc.to_string()) },
+            None => quote! { None },
+        };
+
+        let body = quote! {
+            invocation.check_keywords(&[#(#keyword_names),*])?;
+
+            let args = std::mem::take(&mut invocation.args);
+            if #arity_check {
                 return Err(bottlerocket_settings_sdk::HelperError::Arity {
-                    expected_args: #num_args,
+                    min_args: #min_args,
+                    max_args: #max_args_tokens,
                     provided_args: args.len(),
                 });
             }
 
             // Call the input function with our dynamically generated list of arguments.
-            // We know that `args` is the correct length because we checked above, so we can let
-            // the macro unwrap values that it takes.
+            // We know that there are enough `args` for the required parameters because we
+            // checked above, so we can let the macro unwrap values that it takes. Missing
+            // optional arguments are passed as `null`, variadic arguments are collected
+            // into an array, and keyword arguments are taken from the invocation's hash.
+            // Borrowed parameters are deserialized into owned values which are then lent to
+            // the function.
             let mut args = args.into_iter();
-            #fn_name(#(
-                    {
-                        let arg: #arg_types = match serde_json::from_value(args.next().unwrap()) {
-                            Ok(parsed) => parsed,
-                            Err(e) => return Err(bottlerocket_settings_sdk::HelperError::JSONParse { source: e })
-                        };
-                        arg
+            #(#arg_bindings)*
+            #call
+        };
+
+        let signature = quote! {
+            bottlerocket_settings_sdk::helper::HelperSignature {
+                name: #fn_name_str.to_string(),
+                params: vec![#(
+                    bottlerocket_settings_sdk::helper::HelperParam {
+                        name: #param_names.to_string(),
+                        ty: #param_types,
+                        optional: #optional,
+                        variadic: #variadic,
+                        keyword: #keyword,
                     }
-                ),*)
-                .map_err(|e| bottlerocket_settings_sdk::HelperError::HelperExecute {
-                    source: e.into(),
-                })
-                .and_then(|result| serde_json::to_value(result).map_err(|e| {
-                    bottlerocket_settings_sdk::HelperError::JSONSerialize { source: e }
-                }))
+                ),*],
+                returns: #return_type,
+                doc: #doc,
+                uses_context: #uses_context,
+            }
+        };
+
+        Ok(Self { body, signature })
+    }
+}
+
+/// How a helper parameter is filled from the arguments the helper is called with.
+#[derive(Debug, PartialEq, Eq)]
+enum ArgKind {
+    /// The parameter must be given positionally.
+    Required,
+    /// The parameter is a trailing positional `Option<T>`, which is `None` if not given.
+    Optional,
+    /// The parameter is marked `#[variadic]`, and collects all remaining positional arguments.
+    Variadic,
+    /// The parameter is marked `#[keyword]`, and must be given as a named argument.
+    Keyword,
+    /// The parameter is an `Option<T>` marked `#[keyword]`, which is `None` if not given.
+    OptionalKeyword,
+    /// The parameter is marked `#[helper_context]`, and receives the invocation's context.
+    Context,
+}
+
+/// A parameter of a helper function.
+struct HelperArg {
+    name: String,
+    ty: Type,
+    kind: ArgKind,
+    /// The location of the parameter, for reporting errors.
+    span: proc_macro2::Span,
+}
+
+impl HelperArg {
+    fn is_keyword(&self) -> bool {
+        matches!(self.kind, ArgKind::Keyword | ArgKind::OptionalKeyword)
+    }
+
+    fn is_positional(&self) -> bool {
+        matches!(
+            self.kind,
+            ArgKind::Required | ArgKind::Optional | ArgKind::Variadic
+        )
+    }
+
+    fn is_option(&self) -> bool {
+        matches!(known_type(&self.ty), Some(("Option", _)))
+    }
+}
+
+/// Removes the marker attribute with the given name from a parameter, returning whether it was
+/// present.
+fn take_marker(attrs: &mut Vec<syn::Attribute>, marker: &str) -> bool {
+    let attrs_len = attrs.len();
+    attrs.retain(|attr| !attr.path().is_ident(marker));
+    attrs.len() != attrs_len
+}
+
+/// Collects the parameters of a helper function, removing any `#[variadic]` and `#[keyword]`
+/// markers from it.
+///
+/// If the function is the helper method of an impl block, its `&self` receiver is skipped.
+fn helper_args(sig: &mut Signature, is_method: bool) -> syn::Result<Vec<HelperArg>> {
+    let mut args = Vec::new();
+    for arg in sig.inputs.iter_mut() {
+        let t = match arg {
+            FnArg::Receiver(r) if is_method => {
+                if r.reference.is_none() || r.mutability.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        r,
+                        "template_helper macro requires helper methods to take `&self`",
+                    ));
+                }
+                continue;
+            }
+            FnArg::Receiver(r) => {
+                return Err(syn::Error::new_spanned(
+                    r,
+                    "template_helper macro does not work on methods that take `self`; use it on the impl block instead",
+                ));
+            }
+            FnArg::Typed(t) => t,
+        };
+
+        let is_variadic = take_marker(&mut t.attrs, "variadic");
+        let is_keyword = take_marker(&mut t.attrs, "keyword");
+        let is_context = take_marker(&mut t.attrs, "helper_context");
+        if [is_variadic, is_keyword, is_context]
+            .iter()
+            .filter(|marker| **marker)
+            .count()
+            > 1
+        {
+            return Err(syn::Error::new_spanned(
+                &t,
+                "template_helper macro parameters may only have one of #[variadic], #[keyword], and #[helper_context]",
+            ));
         }
-    })
-    .unwrap();
+        if let Type::Reference(syn::TypeReference {
+            mutability: Some(_),
+            ..
+        }) = &*t.ty
+        {
+            return Err(syn::Error::new_spanned(
+                &t.ty,
+                "template_helper macro does not support `&mut` parameters",
+            ));
+        }
+        if let Type::ImplTrait(_) = &*t.ty {
+            return Err(syn::Error::new_spanned(
+                &t.ty,
+                "template_helper macro does not support `impl Trait` parameters",
+            ));
+        }
+
+        let name = match &*t.pat {
+            syn::Pat::Ident(pat) => pat.ident.to_string(),
+            pat => pat.to_token_stream().to_string(),
+        };
+        let mut arg = HelperArg {
+            name,
+            ty: (*t.ty).clone(),
+            kind: ArgKind::Required,
+            span: t.span(),
+        };
+        arg.kind = match (is_variadic, is_keyword, arg.is_option()) {
+            _ if is_context => ArgKind::Context,
+            (true, _, _) => ArgKind::Variadic,
+            (_, true, true) => ArgKind::OptionalKeyword,
+            (_, true, false) => ArgKind::Keyword,
+            _ => ArgKind::Required,
+        };
+        if arg.kind == ArgKind::Context && !is_helper_context(&arg.ty) {
+            return Err(syn::Error::new_spanned(
+                &t.ty,
+                "#[helper_context] parameters must be of type `HelperContext` or `Option<HelperContext>`",
+            ));
+        }
+        args.push(arg);
+    }
+
+    if let Some(arg) = args
+        .iter()
+        .filter(|arg| arg.is_positional())
+        .rev()
+        .skip(1)
+        .find(|arg| arg.kind == ArgKind::Variadic)
+    {
+        return Err(syn::Error::new(
+            arg.span,
+            "template_helper macro only allows the last positional parameter to be #[variadic]",
+        ));
+    }
+
+    // Trailing positional `Option<T>` parameters, ignoring any variadic parameter, are optional.
+    args.iter_mut()
+        .filter(|arg| arg.is_positional())
+        .rev()
+        .skip_while(|arg| arg.kind == ArgKind::Variadic)
+        .take_while(|arg| arg.is_option())
+        .for_each(|arg| arg.kind = ArgKind::Optional);
+
+    Ok(args)
+}
+
+/// Returns whether a type is `HelperContext` or `Option<HelperContext>`, or a reference to one.
+fn is_helper_context(ty: &Type) -> bool {
+    let path = match ty {
+        Type::Path(path) => path,
+        Type::Reference(r) => return is_helper_context(&r.elem),
+        _ => return false,
+    };
+    match path.path.segments.last() {
+        Some(segment) if segment.ident == "HelperContext" => true,
+        _ => {
+            matches!(known_type(ty), Some(("Option", args)) if args.len() == 1 && is_helper_context(args[0]))
+        }
+    }
+}
+
+/// Generates a statement which binds a helper parameter's value from the invocation to `var`, and
+/// the expression which passes it to the helper function.
+fn arg_binding(arg: &HelperArg, var: &syn::Ident) -> (TokenStream2, TokenStream2) {
+    let name = &arg.name;
+    let (owned_ty, pass) = owned_type(&arg.ty);
+    let pass = match pass {
+        Pass::Value => quote! { #var },
+        Pass::Ref => quote! { &#var },
+        Pass::OptionDeref => quote! { #var.as_deref() },
+        Pass::OptionRef => quote! { #var.as_ref() },
+    };
+    let json_value = match arg.kind {
+        ArgKind::Context if matches!(known_type(&owned_ty), Some(("Option", _))) => {
+            return (
+                quote! { let #var: #owned_ty = invocation.context.clone(); },
+                pass,
+            )
+        }
+        ArgKind::Context => {
+            return (
+                quote! { let #var: #owned_ty = invocation.require_context()?; },
+                pass,
+            )
+        }
+        ArgKind::Required => quote! { args.next().unwrap() },
+        ArgKind::Optional => quote! { args.next().unwrap_or(serde_json::Value::Null) },
+        ArgKind::Variadic => quote! { serde_json::Value::Array(args.by_ref().collect()) },
+        ArgKind::Keyword => quote! { invocation.take_keyword(#name)? },
+        ArgKind::OptionalKeyword => quote! {
+            invocation.hash.remove(#name).unwrap_or(serde_json::Value::Null)
+        },
+    };
+
+    let binding = quote! {
+        let #var: #owned_ty = match serde_json::from_value(#json_value) {
+            Ok(parsed) => parsed,
+            Err(e) => return Err(bottlerocket_settings_sdk::HelperError::JSONParse { source: e })
+        };
+    };
+    (binding, pass)
+}
+
+/// How an owned parameter value is passed to a helper function.
+enum Pass {
+    /// The value is passed as is.
+    Value,
+    /// A reference to the value is passed, e.g. a `&str` borrowed from a `String`.
+    Ref,
+    /// An `Option<T>` is passed as an `Option<&T::Target>`, e.g. `Option<&str>`.
+    OptionDeref,
+    /// An `Option<T>` is passed as an `Option<&T>`.
+    OptionRef,
+}
+
+/// Returns the owned type which a helper parameter's value is deserialized into, and how it's
+/// passed to the helper function.
+///
+/// `&str` is deserialized as `String`, `&[T]` as `Vec<T>`, and `&T` as `T`, including within an
+/// `Option`. Lifetimes are elided from the owned type.
+fn owned_type(ty: &Type) -> (Type, Pass) {
+    let (mut owned, pass) = match ty {
+        Type::Reference(r) => (owned_referent(&r.elem), Pass::Ref),
+        _ => match known_type(ty) {
+            Some(("Option", args)) => match args.first() {
+                Some(Type::Reference(r)) => {
+                    let pass = match is_unsized(&r.elem) {
+                        true => Pass::OptionDeref,
+                        false => Pass::OptionRef,
+                    };
+                    let inner = owned_referent(&r.elem);
+                    (syn::parse_quote!(Option<#inner>), pass)
+                }
+                _ => (ty.clone(), Pass::Value),
+            },
+            _ => (ty.clone(), Pass::Value),
+        },
+    };
+
+    ElideLifetimes.visit_type_mut(&mut owned);
+    (owned, pass)
+}
+
+/// Returns the owned type which can be borrowed as the given type.
+fn owned_referent(ty: &Type) -> Type {
+    match ty {
+        Type::Slice(slice) => {
+            let elem = &slice.elem;
+            syn::parse_quote!(Vec<#elem>)
+        }
+        _ if is_unsized(ty) => syn::parse_quote!(String),
+        _ => ty.clone(),
+    }
+}
+
+/// Returns whether a type is `str` or a slice, which are borrowed from a `String` or `Vec<T>`.
+fn is_unsized(ty: &Type) -> bool {
+    matches!(ty, Type::Slice(_)) || matches!(known_type(ty), Some(("str", _)))
+}
+
+/// Replaces all lifetimes in a type with `'_`, so that it can be used in a `let` binding.
+struct ElideLifetimes;
+
+impl VisitMut for ElideLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        *lifetime = syn::Lifetime::new("'_", lifetime.span());
+    }
+}
+
+/// The value returned by a helper function.
+struct HelperReturn {
+    /// The type which is serialized as the helper's result, e.g. `T` from `Result<T, E>`.
+    ty: Type,
+    /// Whether the function returns a `Result` whose errors are reported as helper failures.
+    fallible: bool,
+}
+
+/// Describes the value returned by a helper function.
+///
+/// Functions may return `Result<T, E>`, or any serializable value, including `impl Serialize`.
+fn helper_return(sig: &Signature) -> syn::Result<HelperReturn> {
+    let ty = match &sig.output {
+        ReturnType::Default => {
+            return Ok(HelperReturn {
+                ty: syn::parse_quote!(()),
+                fallible: false,
+            })
+        }
+        ReturnType::Type(_, ty) => &**ty,
+    };
+
+    match ty {
+        Type::ImplTrait(impl_trait) if !impl_trait.bounds.iter().any(is_serialize_bound) => {
+            Err(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "unsupported return type `{}`; template_helper functions must return `impl Serialize`, a serializable type, or a `Result<T, E>`",
+                    rust_type_name(ty)
+                ),
+            ))
+        }
+        Type::Never(_) | Type::TraitObject(_) | Type::BareFn(_) => Err(syn::Error::new_spanned(
+            ty,
+            format!(
+                "unsupported return type `{}`; template_helper functions must return a serializable type or a `Result<T, E>`",
+                rust_type_name(ty)
+            ),
+        )),
+        _ => Ok(match result_ok_type(ty) {
+            Some(ok_ty) => HelperReturn {
+                ty: ok_ty.clone(),
+                fallible: true,
+            },
+            None => HelperReturn {
+                ty: ty.clone(),
+                fallible: false,
+            },
+        }),
+    }
+}
 
-    if let Some(visibility) = args.vis {
-        helper_fn.vis = syn::parse_str(&visibility).unwrap();
+/// Returns whether a trait bound is `Serialize`.
+fn is_serialize_bound(bound: &syn::TypeParamBound) -> bool {
+    match bound {
+        syn::TypeParamBound::Trait(t) => t
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Serialize"),
+        _ => false,
     }
+}
+
+/// Returns the type `T` from a `Result<T, E>`, if the given type is a `Result`.
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    match known_type(ty)? {
+        ("Result", args) => args.first().copied(),
+        _ => None,
+    }
+}
+
+/// Returns the name and type arguments of a well-known type, such as `Option<T>` or `String`.
+///
+/// Types are recognized by their path, which may be unqualified or qualified with `std`, `core` or
+/// `alloc`. `Value` is also recognized as `serde_json::Value`, and `Result` as a module's `Result`
+/// alias, such as `anyhow::Result<T>` or `io::Result<T>`.
+fn known_type(ty: &Type) -> Option<(&'static str, Vec<&Type>)> {
+    const KNOWN: &[&str] = &[
+        "Result", "Option", "Vec", "VecDeque", "HashSet", "BTreeSet", "HashMap", "BTreeMap", "Box",
+        "String", "str", "char", "bool", "Value", "i8", "i16", "i32", "i64", "i128", "isize", "u8",
+        "u16", "u32", "u64", "u128", "usize", "f32", "f64",
+    ];
+
+    let Type::Path(syn::TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let idents: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    let idents: Vec<&str> = idents.iter().map(String::as_str).collect();
+    let name = match idents[..] {
+        [name] => name,
+        ["std" | "core" | "alloc", .., name] => name,
+        ["serde_json", "Value"] => "Value",
+        [_, "Result"] => "Result",
+        _ => return None,
+    };
+    let name = KNOWN.iter().find(|known| **known == name)?;
+
+    let args = match &path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some((name, args))
+}
+
+/// Generates a `HelperType` describing the given Rust type.
+fn helper_type(ty: &Type) -> TokenStream2 {
+    let rust_type = rust_type_name(ty);
+    let json_type = json_type(ty);
 
     quote! {
-        #fn_ast
+        bottlerocket_settings_sdk::helper::HelperType {
+            rust_type: #rust_type.to_string(),
+            json_type: #json_type,
+        }
+    }
+}
+
+/// Generates a `JsonType` describing how the given Rust type is represented in JSON.
+fn json_type(ty: &Type) -> TokenStream2 {
+    let json = quote! { bottlerocket_settings_sdk::helper::JsonType };
+    let boxed = |ty: Option<&&Type>| {
+        let inner = ty.map_or_else(|| quote! { #json::Any }, |ty| json_type(ty));
+        quote! { Box::new(#inner) }
+    };
+
+    match ty {
+        Type::Reference(r) => json_type(&r.elem),
+        Type::Paren(p) => json_type(&p.elem),
+        Type::Group(g) => json_type(&g.elem),
+        Type::Slice(s) => {
+            let items = json_type(&s.elem);
+            quote! { #json::Array(Box::new(#items)) }
+        }
+        Type::Array(a) => {
+            let items = json_type(&a.elem);
+            quote! { #json::Array(Box::new(#items)) }
+        }
+        Type::Tuple(t) if t.elems.is_empty() => quote! { #json::Null },
+        Type::Tuple(_) => quote! { #json::Array(Box::new(#json::Any)) },
+        _ => match known_type(ty) {
+            Some(("String" | "str" | "char", _)) => quote! { #json::String },
+            Some(("bool", _)) => quote! { #json::Boolean },
+            Some((
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize" | "f32" | "f64",
+                _,
+            )) => quote! { #json::Number },
+            Some(("Option", args)) => {
+                let inner = boxed(args.first());
+                quote! { #json::Nullable(#inner) }
+            }
+            Some(("Vec" | "VecDeque" | "HashSet" | "BTreeSet", args)) => {
+                let inner = boxed(args.first());
+                quote! { #json::Array(#inner) }
+            }
+            Some(("HashMap" | "BTreeMap", args)) => {
+                let inner = boxed(args.get(1));
+                quote! { #json::Object(#inner) }
+            }
+            Some(("Box", args)) if !args.is_empty() => json_type(args[0]),
+            _ => quote! { #json::Any },
+        },
+    }
+}
+
+/// Renders a Rust type as it would usually be written, e.g. `Vec<String>` rather than the
+/// `Vec < String >` produced by the token stream.
+fn rust_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(syn::TypePath { qself: None, path }) => path_name(path),
+        Type::Reference(r) => {
+            let lifetime = r
+                .lifetime
+                .as_ref()
+                .map(|lifetime| format!("{} ", lifetime))
+                .unwrap_or_default();
+            let mutability = if r.mutability.is_some() { "mut " } else { "" };
+            format!("&{}{}{}", lifetime, mutability, rust_type_name(&r.elem))
+        }
+        Type::Ptr(p) => {
+            let mutability = if p.mutability.is_some() {
+                "mut"
+            } else {
+                "const"
+            };
+            format!("*{} {}", mutability, rust_type_name(&p.elem))
+        }
+        Type::Slice(s) => format!("[{}]", rust_type_name(&s.elem)),
+        Type::Array(a) => format!("[{}; {}]", rust_type_name(&a.elem), a.len.to_token_stream()),
+        Type::Tuple(t) if t.elems.len() == 1 => format!("({},)", rust_type_name(&t.elems[0])),
+        Type::Tuple(t) => format!("({})", join_names(t.elems.iter().map(rust_type_name))),
+        Type::Paren(p) => format!("({})", rust_type_name(&p.elem)),
+        Type::Group(g) => rust_type_name(&g.elem),
+        Type::ImplTrait(t) => format!("impl {}", bounds_name(&t.bounds)),
+        Type::TraitObject(t) => format!("dyn {}", bounds_name(&t.bounds)),
+        Type::Never(_) => "!".to_string(),
+        Type::Infer(_) => "_".to_string(),
+        _ => ty.to_token_stream().to_string(),
+    }
+}
 
-        #helper_fn
+/// Renders a path as it would usually be written, e.g. `std::collections::HashMap<String, u8>`.
+fn path_name(path: &syn::Path) -> String {
+    let segments = path.segments.iter().map(|segment| {
+        let arguments = match &segment.arguments {
+            PathArguments::None => String::new(),
+            PathArguments::AngleBracketed(args) => {
+                format!(
+                    "<{}>",
+                    join_names(args.args.iter().map(generic_argument_name))
+                )
+            }
+            PathArguments::Parenthesized(args) => {
+                let output = match &args.output {
+                    ReturnType::Default => String::new(),
+                    ReturnType::Type(_, ty) => format!(" -> {}", rust_type_name(ty)),
+                };
+                format!(
+                    "({}){}",
+                    join_names(args.inputs.iter().map(rust_type_name)),
+                    output
+                )
+            }
+        };
+        format!("{}{}", segment.ident, arguments)
+    });
+
+    let leading_colon = if path.leading_colon.is_some() {
+        "::"
+    } else {
+        ""
+    };
+    format!(
+        "{}{}",
+        leading_colon,
+        segments.collect::<Vec<_>>().join("::")
+    )
+}
+
+/// Renders a generic argument, such as a type or lifetime, as it would usually be written.
+fn generic_argument_name(arg: &GenericArgument) -> String {
+    match arg {
+        GenericArgument::Type(ty) => rust_type_name(ty),
+        GenericArgument::Lifetime(lifetime) => lifetime.to_string(),
+        GenericArgument::AssocType(assoc) => {
+            format!("{} = {}", assoc.ident, rust_type_name(&assoc.ty))
+        }
+        _ => arg.to_token_stream().to_string(),
     }
-    .into()
+}
+
+/// Renders the bounds of an `impl Trait` or `dyn Trait` type, e.g. `Serialize + Send`.
+fn bounds_name<'a>(bounds: impl IntoIterator<Item = &'a syn::TypeParamBound>) -> String {
+    let bounds = bounds.into_iter().map(|bound| match bound {
+        syn::TypeParamBound::Trait(t) => {
+            let maybe = match t.modifier {
+                syn::TraitBoundModifier::Maybe(_) => "?",
+                syn::TraitBoundModifier::None => "",
+            };
+            format!("{}{}", maybe, path_name(&t.path))
+        }
+        syn::TypeParamBound::Lifetime(lifetime) => lifetime.to_string(),
+        _ => bound.to_token_stream().to_string(),
+    });
+    bounds.collect::<Vec<_>>().join(" + ")
+}
+
+/// Joins rendered names into a comma-separated list.
+fn join_names(names: impl Iterator<Item = String>) -> String {
+    names.collect::<Vec<_>>().join(", ")
+}
+
+/// Collects the doc comment attached to a function, if any.
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .collect();
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
 }
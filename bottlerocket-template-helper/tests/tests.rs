@@ -1,5 +1,8 @@
 use anyhow::Result;
-use bottlerocket_settings_sdk::HelperDef;
+use bottlerocket_settings_sdk::helper::{
+    HelperContext, HelperInvocation, HelperParam, HelperSignature, HelperType, JsonType,
+};
+use bottlerocket_settings_sdk::{HelperDef, HelperError};
 use bottlerocket_template_helper::template_helper;
 use serde_json::json;
 
@@ -36,3 +39,452 @@ fn call_no_args() {
     assert_eq!(no_args_helper(vec![]).unwrap(), json!(""));
     assert!(no_args_helper(vec![json!("sneaky arg")]).is_err());
 }
+
+#[template_helper(ident = NoArgsHelper)]
+fn no_args_camel_case() -> Result<String> {
+    Ok(String::new())
+}
+
+#[test]
+fn provided_helper_keeps_signature() {
+    let helpers: std::collections::HashMap<String, Box<dyn HelperDef>> = bottlerocket_settings_sdk::provide_template_helpers! {
+        "join_strings" => join_strings_helper,
+        "no_args" => NoArgsHelper,
+    };
+
+    assert_eq!(
+        helpers["join_strings"].signature(),
+        join_strings_helper.signature()
+    );
+    assert_eq!(
+        helpers["no_args"].signature().unwrap().name,
+        "no_args_camel_case"
+    );
+    assert_eq!(NoArgsHelper(vec![]).unwrap(), json!(""));
+}
+
+/// Joins a list of words with a separator.
+#[template_helper(ident = join_words_helper)]
+fn join_words(words: Vec<String>, separator: Option<char>) -> Result<String> {
+    Ok(words.join(&separator.unwrap_or(' ').to_string()))
+}
+
+#[test]
+fn helper_signature() {
+    let signature = join_words_helper.signature().unwrap();
+
+    assert_eq!(
+        signature,
+        HelperSignature {
+            name: "join_words".to_string(),
+            params: vec![
+                HelperParam {
+                    name: "words".to_string(),
+                    ty: HelperType {
+                        rust_type: "Vec<String>".to_string(),
+                        json_type: JsonType::Array(Box::new(JsonType::String)),
+                    },
+                    optional: false,
+                    variadic: false,
+                    keyword: false,
+                },
+                HelperParam {
+                    name: "separator".to_string(),
+                    ty: HelperType {
+                        rust_type: "Option<char>".to_string(),
+                        json_type: JsonType::Nullable(Box::new(JsonType::String)),
+                    },
+                    optional: true,
+                    variadic: false,
+                    keyword: false,
+                },
+            ],
+            returns: HelperType {
+                rust_type: "String".to_string(),
+                json_type: JsonType::String,
+            },
+            doc: Some("Joins a list of words with a separator.".to_string()),
+            uses_context: false,
+        }
+    );
+    assert_eq!(signature.min_args(), 1);
+    assert_eq!(signature.max_args(), Some(2));
+
+    assert!(no_args_helper.signature().unwrap().params.is_empty());
+    assert_eq!(no_args_helper.signature().unwrap().doc, None);
+}
+
+#[test]
+fn helper_signature_json() {
+    assert_eq!(
+        serde_json::to_value(join_strings_helper.signature().unwrap()).unwrap(),
+        json!({
+            "name": "join_strings",
+            "params": [
+                {
+                    "name": "lhs",
+                    "type": {"rust_type": "String", "json_type": {"type": "string"}},
+                    "optional": false,
+                    "variadic": false,
+                    "keyword": false,
+                },
+                {
+                    "name": "rhs",
+                    "type": {"rust_type": "String", "json_type": {"type": "string"}},
+                    "optional": false,
+                    "variadic": false,
+                    "keyword": false,
+                },
+            ],
+            "returns": {"rust_type": "String", "json_type": {"type": "string"}},
+            "doc": null,
+            "uses_context": false,
+        })
+    );
+}
+
+#[test]
+fn json_type_matches() {
+    let words = JsonType::Nullable(Box::new(JsonType::Array(Box::new(JsonType::String))));
+
+    assert!(words.matches(&json!(null)));
+    assert!(words.matches(&json!(["a", "b"])));
+    assert!(!words.matches(&json!(["a", 1])));
+    assert!(!words.matches(&json!("a")));
+}
+
+#[test]
+fn call_optional_args() {
+    assert_eq!(
+        join_words_helper(vec![json!(["a", "b"])]).unwrap(),
+        json!("a b")
+    );
+    assert_eq!(
+        join_words_helper(vec![json!(["a", "b"]), json!(null)]).unwrap(),
+        json!("a b")
+    );
+    assert_eq!(
+        join_words_helper(vec![json!(["a", "b"]), json!(",")]).unwrap(),
+        json!("a,b")
+    );
+
+    assert!(matches!(
+        join_words_helper(vec![]),
+        Err(HelperError::Arity {
+            min_args: 1,
+            max_args: Some(2),
+            provided_args: 0
+        })
+    ));
+    assert!(join_words_helper(vec![json!([]), json!(","), json!("extra")]).is_err());
+}
+
+#[template_helper(ident = join_all_helper)]
+fn join_all(
+    separator: String,
+    prefix: Option<String>,
+    #[variadic] items: Vec<String>,
+) -> Result<String> {
+    Ok(prefix.unwrap_or_default() + &items.join(&separator))
+}
+
+#[test]
+fn call_variadic_args() {
+    assert_eq!(join_all_helper(vec![json!(",")]).unwrap(), json!(""));
+    assert_eq!(
+        join_all_helper(vec![
+            json!(","),
+            json!(">"),
+            json!("a"),
+            json!("b"),
+            json!("c")
+        ])
+        .unwrap(),
+        json!(">a,b,c")
+    );
+    assert!(join_all_helper(vec![json!(","), json!(">"), json!(1)]).is_err());
+
+    let error = join_all_helper(vec![]).unwrap_err();
+    assert!(matches!(
+        error,
+        HelperError::Arity {
+            min_args: 1,
+            max_args: None,
+            provided_args: 0
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Helper called with incorrect arity: expected at least 1 args, but 0 provided"
+    );
+
+    let signature = join_all_helper.signature().unwrap();
+    assert!(signature.params[1].optional);
+    assert!(signature.params[2].variadic);
+    assert_eq!(signature.max_args(), None);
+    assert!(signature.accepts_arity(10));
+    assert!(!signature.accepts_arity(0));
+}
+
+#[template_helper(ident = greet_helper)]
+fn greet(
+    name: String,
+    #[keyword] greeting: String,
+    #[keyword] punctuation: Option<char>,
+) -> Result<String> {
+    Ok(format!("{greeting}, {name}{}", punctuation.unwrap_or('.')))
+}
+
+#[test]
+fn call_keyword_args() {
+    let invocation =
+        HelperInvocation::new(vec![json!("world")]).with_hash_arg("greeting", json!("Hello"));
+    assert_eq!(
+        greet_helper.execute(invocation.clone()).unwrap(),
+        json!("Hello, world.")
+    );
+    assert_eq!(
+        greet_helper
+            .execute(invocation.clone().with_hash_arg("punctuation", json!("!")))
+            .unwrap(),
+        json!("Hello, world!")
+    );
+
+    assert!(matches!(
+        greet_helper.helper_fn(vec![json!("world")]),
+        Err(HelperError::MissingKeyword { name }) if name == "greeting"
+    ));
+    assert!(matches!(
+        greet_helper.execute(invocation.with_hash_arg("volume", json!(11))),
+        Err(HelperError::UnexpectedKeyword { name }) if name == "volume"
+    ));
+    assert!(matches!(
+        join_strings_helper.execute(HelperInvocation::new(vec![]).with_hash_arg("sep", json!(","))),
+        Err(HelperError::UnexpectedKeyword { .. })
+    ));
+
+    let signature = greet_helper.signature().unwrap();
+    assert_eq!(signature.min_args(), 1);
+    assert_eq!(signature.max_args(), Some(1));
+    assert_eq!(
+        signature
+            .keywords()
+            .map(|p| (p.name.as_str(), p.optional))
+            .collect::<Vec<_>>(),
+        vec![("greeting", false), ("punctuation", true)]
+    );
+}
+
+#[template_helper(ident = describe_helper)]
+fn describe(#[helper_context] context: HelperContext, suffix: Option<String>) -> Result<String> {
+    Ok(format!(
+        "{}: {}{}",
+        context.version,
+        context.value.unwrap_or_default(),
+        suffix.unwrap_or_default()
+    ))
+}
+
+#[test]
+fn call_with_context() {
+    let invocation = HelperInvocation::new(vec![json!("!")])
+        .with_context(HelperContext::new("v2").with_value(json!("hi")));
+    assert_eq!(
+        describe_helper.execute(invocation).unwrap(),
+        json!("v2: \"hi\"!")
+    );
+    assert!(matches!(
+        describe_helper(vec![]),
+        Err(HelperError::MissingContext)
+    ));
+    assert!(matches!(
+        describe_helper.execute(HelperInvocation::new(vec![])),
+        Err(HelperError::MissingContext)
+    ));
+
+    let signature = describe_helper.signature().unwrap();
+    assert!(signature.uses_context);
+    assert_eq!(signature.params.len(), 1);
+    assert_eq!(signature.min_args(), 0);
+}
+
+#[template_helper(ident = version_or_helper)]
+fn version_or(#[helper_context] context: Option<HelperContext>, default: String) -> String {
+    context.map(|context| context.version).unwrap_or(default)
+}
+
+#[test]
+fn call_with_optional_context() {
+    let invocation =
+        HelperInvocation::new(vec![json!("none")]).with_context(HelperContext::new("v1"));
+    assert_eq!(version_or_helper.execute(invocation).unwrap(), json!("v1"));
+    assert_eq!(
+        version_or_helper(vec![json!("none")]).unwrap(),
+        json!("none")
+    );
+}
+
+struct Repeater {
+    times: usize,
+}
+
+#[template_helper]
+impl Repeater {
+    fn times(&self) -> usize {
+        self.times
+    }
+
+    /// Repeats a string.
+    #[helper]
+    fn repeat(&self, s: String, #[keyword] sep: Option<String>) -> Result<String> {
+        Ok(vec![s; self.times()].join(&sep.unwrap_or_default()))
+    }
+}
+
+#[test]
+fn call_method_helper() {
+    let helper = Repeater { times: 3 };
+    assert_eq!(helper.helper_fn(vec![json!("a")]).unwrap(), json!("aaa"));
+    assert_eq!(
+        helper
+            .execute(HelperInvocation::new(vec![json!("a")]).with_hash_arg("sep", json!(",")))
+            .unwrap(),
+        json!("a,a,a")
+    );
+    assert!(matches!(
+        helper.helper_fn(vec![]),
+        Err(HelperError::Arity { .. })
+    ));
+
+    // The receiver is not described as a parameter.
+    let signature = helper.signature().unwrap();
+    assert_eq!(signature.name, "repeat");
+    assert_eq!(signature.doc.as_deref(), Some("Repeats a string."));
+    assert_eq!(
+        signature
+            .params
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["s", "sep"]
+    );
+}
+
+#[template_helper(ident = shout_helper)]
+fn shout(s: &str, suffix: Option<&str>) -> String {
+    s.to_uppercase() + suffix.unwrap_or("!")
+}
+
+#[template_helper(ident = longest_helper)]
+fn longest<'a>(
+    #[variadic] words: &'a [String],
+    #[keyword] fallback: Option<&'a String>,
+) -> Option<&'a String> {
+    words.iter().max_by_key(|word| word.len()).or(fallback)
+}
+
+#[template_helper(ident = pair_helper)]
+fn pair(key: &str, #[keyword] value: &serde_json::Value) -> impl serde::Serialize {
+    json!({ key: value })
+}
+
+#[template_helper(ident = nothing_helper)]
+fn nothing() {}
+
+#[test]
+fn call_infallible_and_borrowing() {
+    assert_eq!(shout_helper(vec![json!("hi")]).unwrap(), json!("HI!"));
+    assert_eq!(
+        shout_helper(vec![json!("hi"), json!("?")]).unwrap(),
+        json!("HI?")
+    );
+    assert_eq!(
+        longest_helper(vec![json!("a"), json!("abc"), json!("ab")]).unwrap(),
+        json!("abc")
+    );
+    assert_eq!(longest_helper(vec![]).unwrap(), json!(null));
+    assert_eq!(
+        longest_helper
+            .execute(HelperInvocation::new(vec![]).with_hash_arg("fallback", json!("none")))
+            .unwrap(),
+        json!("none")
+    );
+    assert_eq!(
+        pair_helper
+            .execute(HelperInvocation::new(vec![json!("a")]).with_hash_arg("value", json!(1)))
+            .unwrap(),
+        json!({"a": 1})
+    );
+    assert_eq!(nothing_helper(vec![]).unwrap(), json!(null));
+
+    // Signatures describe the parameters as written.
+    let signature = shout_helper.signature().unwrap();
+    assert_eq!(signature.params[0].ty.rust_type, "&str");
+    assert_eq!(signature.params[0].ty.json_type, JsonType::String);
+    assert!(signature.params[1].optional);
+    assert_eq!(signature.returns.rust_type, "String");
+    assert_eq!(
+        longest_helper.signature().unwrap().params[0].ty.json_type,
+        JsonType::Array(Box::new(JsonType::String))
+    );
+}
+
+mod custom {
+    /// A type which shares its name with `std::option::Option`, but is not optional.
+    #[derive(serde::Deserialize, serde::Serialize)]
+    pub struct Option<T>(pub T);
+}
+
+#[template_helper(ident = count_helper)]
+fn count(
+    counts: std::collections::BTreeMap<String, u8>,
+    wrapped: custom::Option<String>,
+) -> std::result::Result<Vec<&'static str>, anyhow::Error> {
+    Ok(vec![
+        "x";
+        counts
+            .get(&wrapped.0)
+            .copied()
+            .unwrap_or_default()
+            .into()
+    ])
+}
+
+#[test]
+fn helper_signature_types() {
+    // Known types are recognized by their path, rather than just the last segment.
+    let signature = count_helper.signature().unwrap();
+    assert_eq!(
+        signature.params[0].ty,
+        HelperType {
+            rust_type: "std::collections::BTreeMap<String, u8>".to_string(),
+            json_type: JsonType::Object(Box::new(JsonType::Number)),
+        }
+    );
+    assert_eq!(
+        signature.params[1].ty,
+        HelperType {
+            rust_type: "custom::Option<String>".to_string(),
+            json_type: JsonType::Any,
+        }
+    );
+    assert!(!signature.params[1].optional);
+    assert_eq!(
+        signature.returns,
+        HelperType {
+            rust_type: "Vec<&'static str>".to_string(),
+            json_type: JsonType::Array(Box::new(JsonType::String)),
+        }
+    );
+
+    assert_eq!(
+        count_helper(vec![json!({"a": 2}), json!("a")]).unwrap(),
+        json!(["x", "x"])
+    );
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
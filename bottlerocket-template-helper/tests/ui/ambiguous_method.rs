@@ -0,0 +1,16 @@
+use bottlerocket_template_helper::template_helper;
+
+struct Exclaimer;
+
+#[template_helper]
+impl Exclaimer {
+    fn exclaim(&self, s: String) -> anyhow::Result<String> {
+        Ok(s + "!")
+    }
+
+    fn question(&self, s: String) -> anyhow::Result<String> {
+        Ok(s + "?")
+    }
+}
+
+fn main() {}
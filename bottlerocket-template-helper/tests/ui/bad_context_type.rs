@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = describe_helper)]
+fn describe(#[helper_context] context: String) -> anyhow::Result<String> {
+    Ok(context)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = join_helper)]
+fn join(#[variadic] #[keyword] items: Vec<String>) -> anyhow::Result<String> {
+    Ok(items.join(","))
+}
+
+fn main() {}
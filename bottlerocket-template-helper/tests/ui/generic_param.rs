@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = show_helper)]
+fn show<T: std::fmt::Display>(value: T) -> String {
+    value.to_string()
+}
+
+fn main() {}
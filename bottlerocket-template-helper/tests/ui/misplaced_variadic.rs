@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = join_helper)]
+fn join(#[variadic] items: Vec<String>, sep: String) -> anyhow::Result<String> {
+    Ok(items.join(&sep))
+}
+
+fn main() {}
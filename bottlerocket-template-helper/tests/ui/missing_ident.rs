@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper]
+fn exclaim(s: String) -> anyhow::Result<String> {
+    Ok(s + "!")
+}
+
+fn main() {}
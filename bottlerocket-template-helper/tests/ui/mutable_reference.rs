@@ -0,0 +1,9 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = exclaim_helper)]
+fn exclaim(s: &mut String) -> String {
+    s.push('!');
+    s.clone()
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = exclaim_helper)]
+struct Exclaim;
+
+fn main() {}
@@ -0,0 +1,12 @@
+use bottlerocket_template_helper::template_helper;
+
+struct Exclaimer;
+
+impl Exclaimer {
+    #[template_helper(ident = exclaim_helper)]
+    fn exclaim(&self, s: String) -> anyhow::Result<String> {
+        Ok(s + "!")
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use bottlerocket_template_helper::template_helper;
+
+#[template_helper(ident = exclaim_helper)]
+fn exclaim(s: String) -> impl std::fmt::Display {
+    s + "!"
+}
+
+fn main() {}